@@ -3,8 +3,8 @@
 //! you to create invalid H3 indices. Cell is higher level and enforces
 //! invariants.
 
-use crate::{Error, Result};
-use std::{convert::TryFrom, fmt};
+use crate::{error::InvalidCell, Error, Result};
+use std::{convert::TryFrom, fmt, str::FromStr};
 
 /// A low-level type for H3 [index manipulation].
 ///
@@ -21,6 +21,20 @@ use std::{convert::TryFrom, fmt};
 pub struct Index(u64);
 
 impl Index {
+    /// Returns a new Index wrapping `raw`, without any validation.
+    #[cfg(feature = "geo")]
+    #[inline]
+    pub(crate) const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw [u64] bits of this index.
+    #[cfg(feature = "geo")]
+    #[inline]
+    pub(crate) const fn into_raw(self) -> u64 {
+        self.0
+    }
+
     /// Returns this index's reserved bit.
     ///
     /// Must always be 0 to remain valid.
@@ -44,7 +58,6 @@ impl Index {
     /// Returns the mode-dependent bits.
     ///
     /// Interpretation of this value depends on the mode bits' value.
-    #[allow(dead_code)]
     #[inline]
     pub const fn mode_dep(self) -> u8 {
         (self.0 >> 0x38) as u8 & 0b111
@@ -147,7 +160,12 @@ impl Cell {
     /// # Errors
     ///
     /// Returns an error if u64 is not a valid [bit-representation] of
-    /// an H3 cell (mode 1 H3 index).
+    /// an H3 cell (mode 1 H3 index), or if it is a descendant of a
+    /// pentagon along the pentagon's deleted k-axis subsequence.
+    ///
+    /// This only performs the checks needed to place a cell in a
+    /// tree. Use [`from_raw_strict`][Cell::from_raw_strict] to fully
+    /// validate untrusted input.
     ///
     /// [bit-representation]: https://h3geo.org/docs/core-library/h3Indexing/
     #[inline]
@@ -159,7 +177,9 @@ impl Cell {
         // we only care about mode 1 (cell) indices
         idx.mode() == 1 &&
         // there are only 122 base cells
-        idx.base() < 122
+        idx.base() < 122 &&
+        // pentagons have no k-axis children
+        !in_deleted_subsequence(idx)
         {
             Ok(Cell(idx.0))
         } else {
@@ -167,6 +187,67 @@ impl Cell {
         }
     }
 
+    /// Constructs a new Cell from a raw [u64] H3 index, validating
+    /// every field of the index.
+    ///
+    /// In addition to the checks performed by
+    /// [`from_raw`][Cell::from_raw], this verifies that the
+    /// mode-dependent bits are 0, every digit up to the cell's
+    /// resolution is in `[0, 6]`, and every unused digit after it is
+    /// 7.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCell`] describing the first check which
+    /// failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, Error, InvalidCell};
+    ///
+    /// assert!(Cell::from_raw_strict(0x85283473fffffff).is_ok());
+    /// // A res-5 cell with a res-6 digit of 0 instead of 7.
+    /// assert!(Cell::from_raw(0x852834707ffffff).is_ok());
+    /// assert!(matches!(
+    ///     Cell::from_raw_strict(0x852834707ffffff),
+    ///     Err(Error::InvalidCell(_, InvalidCell::UnusedDigit(6)))
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn from_raw_strict(raw: u64) -> Result<Self> {
+        let idx = Index(raw);
+        let reason = if idx.reserved() {
+            InvalidCell::Reserved
+        } else if idx.mode() != 1 {
+            InvalidCell::Mode(idx.mode())
+        } else if idx.mode_dep() != 0 {
+            InvalidCell::ModeDependent(idx.mode_dep())
+        } else if (raw >> 0x2D) as u8 & 0b111_1111 >= 122 {
+            InvalidCell::Base((raw >> 0x2D) as u8 & 0b111_1111)
+        } else {
+            let res = idx.res();
+            let mut r = 1;
+            loop {
+                if r > 15 {
+                    break if in_deleted_subsequence(idx) {
+                        InvalidCell::DeletedSubsequence
+                    } else {
+                        return Ok(Cell(raw));
+                    };
+                }
+                match idx.digit(r) {
+                    Some(7) if r <= res => break InvalidCell::Digit(r),
+                    Some(digit) if r > res && digit != 7 => break InvalidCell::UnusedDigit(r),
+                    _ => r += 1,
+                }
+            }
+        };
+        Err(Error::InvalidCell(raw, reason))
+    }
+
     /// Returns the raw [u64] H3 index for this cell.
     #[inline]
     pub const fn into_raw(self) -> u64 {
@@ -193,6 +274,159 @@ impl Cell {
         }
     }
 
+    /// Returns an iterator over this cell's children at `res`, in
+    /// digit order.
+    ///
+    /// The iterator is empty if `res` is coarser than this cell's
+    /// resolution, and only yields `self` if they're equal. Children
+    /// of pentagons which fall in the deleted k-axis subsequence are
+    /// skipped, so a pentagon has only 6 children at the next
+    /// resolution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// let children = parent.children(3).collect::<Vec<Cell>>();
+    /// assert_eq!(children.len(), 7);
+    /// assert_eq!(children[0], Cell::from_raw(0x835990fffffffff)?);
+    /// assert_eq!(children[6], Cell::from_raw(0x835996fffffffff)?);
+    /// assert_eq!(parent.children(4).count(), 49);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn children(&self, res: u8) -> impl Iterator<Item = Cell> {
+        Children::new(*self, res)
+    }
+
+    /// Returns this cell's center child at `res`.
+    ///
+    /// Returns `None` if `res` is coarser than this cell's resolution.
+    #[inline]
+    pub fn center_child(&self, res: u8) -> Option<Self> {
+        Children::new(*self, res).next()
+    }
+
+    /// Returns the exact number of children this cell has at `res`.
+    ///
+    /// Returns 0 if `res` is coarser than this cell's resolution.
+    pub fn children_count(&self, res: u8) -> u64 {
+        if res < self.res() || res > 15 {
+            return 0;
+        }
+        let n = res - self.res();
+        if self.is_pentagon() {
+            pentagon_children_count(n)
+        } else {
+            7_u64.pow(n as u32)
+        }
+    }
+
+    /// Returns the position of this cell within the ordered
+    /// [children][Cell::children] of its parent at `parent_res`.
+    ///
+    /// Returns `None` if `parent_res` is finer than this cell's
+    /// resolution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// for (position, child) in parent.children(4).enumerate() {
+    ///     assert_eq!(child.child_position(2), Some(position as u64));
+    ///     assert_eq!(parent.child_at_position(position as u64, 4), Some(child));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn child_position(&self, parent_res: u8) -> Option<u64> {
+        let parent = self.to_parent(parent_res)?;
+        let idx = Index(self.0);
+        let digit = |res| u64::from(idx.digit(res).expect("res is in [1,15]"));
+        if !parent.is_pentagon() {
+            return Some(((parent_res + 1)..=self.res()).fold(0, |acc, res| acc * 7 + digit(res)));
+        }
+        // A pentagon's children are its center child's children,
+        // followed by the children of each of its non-center children,
+        // skipping the deleted k-axis digit.
+        let leading_res = match ((parent_res + 1)..=self.res()).find(|&res| digit(res) != 0) {
+            None => return Some(0),
+            Some(res) => res,
+        };
+        let n = self.res() - leading_res;
+        let leading_offset = digit(leading_res).checked_sub(2)?;
+        let rest = ((leading_res + 1)..=self.res()).fold(0, |acc, res| acc * 7 + digit(res));
+        Some(pentagon_children_count(n) + leading_offset * 7_u64.pow(n as u32) + rest)
+    }
+
+    /// Returns the child at `position` within this cell's ordered
+    /// [children][Cell::children] at `res`.
+    ///
+    /// Returns `None` if `res` is coarser than this cell's resolution
+    /// or `position` is out of range.
+    pub fn child_at_position(&self, position: u64, res: u8) -> Option<Self> {
+        if position >= self.children_count(res) {
+            return None;
+        }
+        let parent_res = self.res();
+        let mut idx = Index(self.0).set_res(res);
+        let set_digits = |mut idx: Index, from_res: u8, mut value: u64| {
+            for res in (from_res..=res).rev() {
+                idx = idx.set_digit(res, (value % 7) as u8);
+                value /= 7;
+            }
+            idx
+        };
+        if !self.is_pentagon() {
+            idx = set_digits(idx, parent_res + 1, position);
+        } else {
+            idx = set_digits(idx, parent_res + 1, 0);
+            if position > 0 {
+                let mut n = 0;
+                while pentagon_children_count(n + 1) <= position {
+                    n += 1;
+                }
+                let offset = position - pentagon_children_count(n);
+                let pow = 7_u64.pow(n as u32);
+                idx = idx.set_digit(res - n, (2 + offset / pow) as u8);
+                idx = set_digits(idx, res - n + 1, offset % pow);
+            }
+        }
+        Some(Cell(idx.0))
+    }
+
+    /// Returns `true` if this cell is one of the 12 pentagons at its
+    /// resolution.
+    ///
+    /// Pentagons only have 6 children: the child which would lie along
+    /// the k-axis (digit 1) is deleted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let pentagon = Cell::from_raw(0x8009fffffffffff)?;
+    /// assert!(pentagon.is_pentagon());
+    /// assert_eq!(pentagon.children(1).count(), 6);
+    /// assert!(pentagon.center_child(5).unwrap().is_pentagon());
+    /// assert!(!Cell::from_raw(0x85283473fffffff)?.is_pentagon());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn is_pentagon(&self) -> bool {
+        let idx = Index(self.0);
+        is_pentagon_base(idx.base()) && (1..=self.res()).all(|res| idx.digit(res) == Some(0))
+    }
+
     /// Returns this cell's base (res-0 parent).
     #[inline]
     pub(crate) const fn base(&self) -> u8 {
@@ -226,6 +460,173 @@ impl Cell {
     }
 }
 
+#[cfg(feature = "geo")]
+impl Cell {
+    /// Returns the resolution `res` cell containing the point at
+    /// `lat`, `lng` degrees.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LatLng`] if either coordinate is not finite,
+    /// or [`Error::Resolution`] if `res` is greater than 15.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// // Monaco.
+    /// let cell = Cell::from_latlng(43.73631, 7.42418, 12)?;
+    /// assert_eq!(cell, Cell::from_raw(0x8c3969a41da15ff)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_latlng(lat: f64, lng: f64, res: u8) -> Result<Self> {
+        if res > 15 {
+            return Err(Error::Resolution(res));
+        }
+        let point = crate::geo::Gnomonic::new(lat, lng).ok_or(Error::LatLng(lat, lng))?;
+        Ok(Cell(point.to_index(res).into_raw()))
+    }
+
+    /// Returns the `(lat, lng)` degrees of this cell's center.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let cell = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let (lat, lng) = cell.to_latlng();
+    /// assert_eq!(Cell::from_latlng(lat, lng, cell.res())?, cell);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_latlng(&self) -> (f64, f64) {
+        crate::geo::index_to_latlng(Index(self.0))
+    }
+
+    /// Returns the `(lat, lng)` degrees of this cell's boundary,
+    /// counterclockwise and not closed.
+    ///
+    /// Besides its corners, a cell spanning an edge of the underlying
+    /// icosahedron has an extra vertex where each such edge crosses
+    /// its boundary, so a boundary can have up to 10 vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let hexagon = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// assert_eq!(hexagon.boundary().len(), 6);
+    ///
+    /// let pentagon = Cell::from_raw(0x8009fffffffffff)?;
+    /// assert_eq!(pentagon.boundary().len(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn boundary(&self) -> Vec<(f64, f64)> {
+        crate::geo::index_to_boundary(Index(self.0))
+            .into_iter()
+            .map(|vertex| vertex.latlng)
+            .collect()
+    }
+
+    /// Returns the cells sharing an edge with this one: six for a
+    /// hexagon, five for a pentagon.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let hexagon = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// assert_eq!(hexagon.neighbors().len(), 6);
+    ///
+    /// let pentagon = Cell::from_raw(0x8009fffffffffff)?;
+    /// assert_eq!(pentagon.neighbors().len(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn neighbors(&self) -> Vec<Cell> {
+        crate::geo::neighbors(*self)
+    }
+
+    /// Returns the cells at most `k` steps from this one, including
+    /// itself, in order of increasing distance.
+    ///
+    /// A disk around a hexagon has `3k(k + 1) + 1` cells, but fewer
+    /// when a pentagon is inside it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let cell = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let disk = cell.grid_disk(2);
+    /// assert_eq!(disk.len(), 19);
+    /// assert_eq!(disk[0], cell);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn grid_disk(&self, k: u32) -> Vec<Cell> {
+        crate::geo::Disk::new(*self, k)
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    /// Returns the cells exactly `k` steps from this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let cell = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// assert_eq!(cell.grid_ring(0), vec![cell]);
+    /// assert_eq!(cell.grid_ring(2).len(), 12);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn grid_ring(&self, k: u32) -> Vec<Cell> {
+        crate::geo::ring(*self, k)
+    }
+
+    /// Returns the number of steps between this cell and `other`.
+    ///
+    /// Like H3, this unfolds the grid around the two cells, so returns
+    /// `None` if they are at different resolutions, too far apart, or
+    /// on opposite sides of a pentagon.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let cell = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// for (k, ring) in (0..4).map(|k| (k, cell.grid_ring(k))) {
+    ///     assert!(ring.iter().all(|other| cell.grid_distance(other) == Some(k)));
+    /// }
+    ///
+    /// let sydney = Cell::from_latlng(-33.8568, 151.2153, 12)?;
+    /// assert_eq!(cell.grid_distance(&sydney), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn grid_distance(&self, other: &Self) -> Option<u32> {
+        crate::geo::grid_distance(*self, *other)
+    }
+}
+
 impl TryFrom<u64> for Cell {
     type Error = Error;
 
@@ -242,6 +643,151 @@ impl TryFrom<i64> for Cell {
     }
 }
 
+/// The digit of a pentagon's deleted child.
+pub(crate) const K_AXIS_DIGIT: u8 = 1;
+
+/// Returns `true` if resolution-0 cell `base` is a pentagon.
+#[inline]
+pub(crate) const fn is_pentagon_base(base: u8) -> bool {
+    matches!(
+        base,
+        4 | 14 | 24 | 38 | 49 | 58 | 63 | 72 | 83 | 97 | 107 | 117
+    )
+}
+
+/// Returns `true` if `idx` lies under a pentagon base cell and its
+/// first non-zero digit is the deleted k-axis digit, 1.
+const fn in_deleted_subsequence(idx: Index) -> bool {
+    if !is_pentagon_base(idx.base()) {
+        return false;
+    }
+    let mut res = 1;
+    while res <= idx.res() {
+        match idx.digit(res) {
+            Some(0) => res += 1,
+            Some(K_AXIS_DIGIT) => return true,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Returns the number of children a pentagon has `n` resolutions
+/// below it.
+///
+/// Each resolution contains one pentagon child and 5 hexagon children
+/// per pentagon of the previous resolution.
+const fn pentagon_children_count(n: u8) -> u64 {
+    1 + 5 * (7_u64.pow(n as u32) - 1) / 6
+}
+
+impl FromStr for Cell {
+    type Err = Error;
+
+    /// Parses a cell from its canonical hexadecimal string
+    /// representation.
+    ///
+    /// The string must be exactly 15 hexadecimal digits, as every
+    /// cell is when formatted with [`Display`][fmt::Display], but may
+    /// be optionally prefixed with `0x` and is case-insensitive. Zero
+    /// padded or truncated strings are rejected. The parsed index is
+    /// validated with [`from_raw_strict`][Cell::from_raw_strict].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::Cell;
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// assert_eq!("8c1fb46741ae9ff".parse::<Cell>()?, eiffel_tower_res12);
+    /// assert_eq!("0x8C1FB46741AE9FF".parse::<Cell>()?, eiffel_tower_res12);
+    /// assert_eq!(eiffel_tower_res12.to_string().parse::<Cell>()?, eiffel_tower_res12);
+    /// assert!("08c1fb46741ae9ff".parse::<Cell>().is_err());
+    /// assert!("Eiffel Tower".parse::<Cell>().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn from_str(s: &str) -> Result<Cell> {
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        // A cell's mode bits put its first digit 15 from the end. This
+        // also rules out the leading sign `from_str_radix` accepts.
+        if digits.len() != 15 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(Error::ParseCell(s.to_owned()));
+        }
+        let raw = u64::from_str_radix(digits, 16).map_err(|_| Error::ParseCell(s.to_owned()))?;
+        Cell::from_raw_strict(raw)
+    }
+}
+
+/// An iterator over a cell's descendants at a fixed resolution.
+///
+/// Children are generated in digit order by treating the digits below
+/// the parent's resolution as an odometer.
+pub(crate) struct Children {
+    parent_res: u8,
+    pentagon: bool,
+    next: Option<Cell>,
+}
+
+impl Children {
+    pub(crate) fn new(parent: Cell, res: u8) -> Self {
+        let parent_res = parent.res();
+        let next = if res < parent_res || res > 15 {
+            None
+        } else {
+            let mut idx = Index(parent.0).set_res(res);
+            for r in (parent_res + 1)..=res {
+                idx = idx.set_digit(r, 0);
+            }
+            Some(Cell(idx.0))
+        };
+        Self {
+            parent_res,
+            pentagon: parent.is_pentagon(),
+            next,
+        }
+    }
+}
+
+impl Iterator for Children {
+    type Item = Cell;
+
+    fn next(&mut self) -> Option<Cell> {
+        let cell = self.next?;
+        let mut idx = Index(cell.0);
+        let mut res = cell.res();
+        self.next = loop {
+            if res == self.parent_res {
+                break None;
+            }
+            match idx.digit(res) {
+                Some(digit) if digit < 6 => {
+                    idx = idx.set_digit(res, digit + 1);
+                    // The odometer reaches the deleted k-axis
+                    // subsequence of a pentagon with only zeros above
+                    // and below the 1, so skip straight to 2.
+                    if self.pentagon
+                        && digit + 1 == K_AXIS_DIGIT
+                        && ((self.parent_res + 1)..res).all(|res| idx.digit(res) == Some(0))
+                    {
+                        idx = idx.set_digit(res, K_AXIS_DIGIT + 1);
+                    }
+                    break Some(Cell(idx.0));
+                }
+                _ => {
+                    idx = idx.set_digit(res, 0);
+                    res -= 1;
+                }
+            }
+        };
+        Some(cell)
+    }
+}
+
 /// A type for building up Cells in an iterative manner when
 /// tree-walking.
 pub(crate) struct CellStack(Option<Cell>);
@@ -315,6 +861,33 @@ impl From<Cell> for CellStack {
     }
 }
 
+impl Ord for Cell {
+    /// Orders cells depth-first in post-order, HexTree's own order.
+    ///
+    /// Cells are compared base cell first, then digit by digit from
+    /// the coarsest resolution down, with unused digits (7) sorting
+    /// after every real digit. This makes every descendant of a cell
+    /// sort before the cell itself, and it is exactly the order in
+    /// which [HexTreeMap][crate::HexTreeMap] visits its leaves.
+    ///
+    /// This differs from comparing [`into_raw`][Cell::into_raw]
+    /// values, where the resolution bits come before the base cell
+    /// and digits, so cells sort by resolution first and every cell
+    /// sorts before its descendants.
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        const RES_MASK: u64 = !(0b1111 << 0x34);
+        (self.0 & RES_MASK)
+            .cmp(&(other.0 & RES_MASK))
+            .then(self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl fmt::Debug for Cell {
     /// [H3 Index](https://h3geo.org/docs/core-library/h3Indexing/):
     /// > The canonical string representation of an H3Index is the
@@ -384,4 +957,171 @@ mod tests {
         assert_eq!(parent_idx.digit(1), Some(7));
         assert_eq!(parent_idx.base(), 20);
     }
+
+    fn assert_children_match_h3o(cellidx: h3o::CellIndex, res: h3o::Resolution) {
+        let cell = Cell::from_raw(u64::from(cellidx)).unwrap();
+        let res_u8 = u8::from(res);
+        let expected = cellidx
+            .children(res)
+            .map(|ci| Cell::from_raw(u64::from(ci)).unwrap())
+            .collect::<Vec<_>>();
+        let actual = cell.children(res_u8).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        assert_eq!(cell.children_count(res_u8), cellidx.children_count(res));
+        assert_eq!(
+            cell.center_child(res_u8).map(Cell::into_raw),
+            cellidx.center_child(res).map(u64::from)
+        );
+        for (position, (child, childidx)) in actual.iter().zip(cellidx.children(res)).enumerate() {
+            let position = position as u64;
+            assert_eq!(
+                child.child_position(cell.res()),
+                childidx.child_position(cellidx.resolution())
+            );
+            assert_eq!(child.child_position(cell.res()), Some(position));
+            assert_eq!(cell.child_at_position(position, res_u8), Some(*child));
+        }
+        let count = cell.children_count(res_u8);
+        assert_eq!(cell.child_at_position(count, res_u8), None);
+    }
+
+    #[test]
+    fn test_children() {
+        use h3o::{CellIndex, Resolution};
+        use std::convert::TryFrom;
+
+        let hexagon = CellIndex::try_from(0x85283473fffffff).unwrap();
+        assert_children_match_h3o(hexagon, Resolution::Five);
+        assert_children_match_h3o(hexagon, Resolution::Six);
+        assert_children_match_h3o(hexagon, Resolution::Eight);
+
+        let hexagon = Cell::from_raw(0x85283473fffffff).unwrap();
+        assert_eq!(hexagon.children(4).count(), 0);
+        assert_eq!(hexagon.children_count(4), 0);
+        assert_eq!(hexagon.center_child(4), None);
+        assert_eq!(hexagon.child_position(6), None);
+
+        let pentagons = CellIndex::base_cells().filter(|cell| cell.is_pentagon());
+        let mut count = 0;
+        for pentagon in pentagons {
+            count += 1;
+            assert!(Cell::from_raw(u64::from(pentagon)).unwrap().is_pentagon());
+            assert_children_match_h3o(pentagon, Resolution::One);
+            assert_children_match_h3o(pentagon, Resolution::Three);
+            let res2 = pentagon.center_child(Resolution::Two).unwrap();
+            assert_children_match_h3o(res2, Resolution::Five);
+            let hexagon_child = pentagon.children(Resolution::Two).last().unwrap();
+            assert_children_match_h3o(hexagon_child, Resolution::Four);
+        }
+        assert_eq!(count, 12);
+    }
+
+    #[test]
+    fn test_from_raw_strict() {
+        let valid = Index(0x85283473fffffff);
+        assert!(Cell::from_raw_strict(valid.0).is_ok());
+        let assert_invalid = |idx: Index, expected: InvalidCell| match Cell::from_raw_strict(idx.0)
+        {
+            Err(Error::InvalidCell(raw, reason)) => {
+                assert_eq!(raw, idx.0);
+                assert_eq!(reason, expected);
+            }
+            other => panic!("expected {:?}, got {:?}", expected, other),
+        };
+        assert_invalid(Index(valid.0 | 1 << 0x3F), InvalidCell::Reserved);
+        assert_invalid(Index(valid.0 ^ 0b11 << 0x3B), InvalidCell::Mode(2));
+        assert_invalid(
+            Index(valid.0 | 0b101 << 0x38),
+            InvalidCell::ModeDependent(5),
+        );
+        assert_invalid(Index(valid.0 | 0b111_1111 << 0x2D), InvalidCell::Base(127));
+        assert_invalid(valid.set_digit(3, 7), InvalidCell::Digit(3));
+        assert_invalid(valid.set_digit(14, 0), InvalidCell::UnusedDigit(14));
+        let pentagon = Index(0x8009fffffffffff);
+        assert!(Cell::from_raw_strict(pentagon.0).is_ok());
+        assert_invalid(
+            pentagon.set_res(2).set_digit(1, 0).set_digit(2, 1),
+            InvalidCell::DeletedSubsequence,
+        );
+        // Strict validation is a superset of the lenient checks.
+        assert!(Cell::from_raw(valid.set_digit(14, 0).0).is_ok());
+    }
+
+    #[test]
+    fn test_from_str() {
+        let cell = Cell::from_raw(0x85283473fffffff).unwrap();
+        assert_eq!("85283473fffffff".parse::<Cell>().unwrap(), cell);
+        assert_eq!("85283473FFFFFFF".parse::<Cell>().unwrap(), cell);
+        assert_eq!("0x85283473fffffff".parse::<Cell>().unwrap(), cell);
+        assert_eq!("0X85283473fffffff".parse::<Cell>().unwrap(), cell);
+        for malformed in [
+            "",
+            "0x",
+            "+85283473fffffff",
+            "85283473fffffffg",
+            "0x0x8528",
+            " 8528",
+            " 85283473fffffff",
+            // Truncated or zero padded.
+            "85283473ffffff",
+            "085283473fffffff",
+            "0x085283473fffffff",
+            "0000085283473fffffff",
+            // Too long.
+            "185283473fffffff",
+            "1285283473fffffff",
+        ] {
+            assert!(
+                matches!(malformed.parse::<Cell>(), Err(Error::ParseCell(ref s)) if s == malformed),
+                "{:?}",
+                malformed
+            );
+        }
+        assert!(matches!(
+            "85283473ffffff0".parse::<Cell>(),
+            Err(Error::InvalidCell(_, InvalidCell::UnusedDigit(_)))
+        ));
+    }
+
+    #[test]
+    fn test_from_raw_rejects_deleted_subsequence() {
+        // Base cell 4 is a pentagon.
+        let pentagon = Index(0x8009fffffffffff);
+        assert!(Cell::from_raw(pentagon.0).is_ok());
+        let k_axis = pentagon.set_res(1).set_digit(1, 1);
+        assert!(Cell::from_raw(k_axis.0).is_err());
+        let below_k_axis = pentagon
+            .set_res(3)
+            .set_digit(1, 0)
+            .set_digit(2, 1)
+            .set_digit(3, 5);
+        assert!(Cell::from_raw(below_k_axis.0).is_err());
+        let hexagon_k_axis = pentagon.set_res(2).set_digit(1, 2).set_digit(2, 1);
+        assert!(Cell::from_raw(hexagon_k_axis.0).is_ok());
+        // Base cell 20 is a hexagon.
+        let hexagon = Index(0x8029fffffffffff).set_res(1).set_digit(1, 1);
+        assert!(Cell::from_raw(hexagon.0).is_ok());
+    }
+
+    #[test]
+    fn test_cell_ord() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let first_child = Cell::from_raw(0x835990fffffffff).unwrap();
+        let last_child = Cell::from_raw(0x835996fffffffff).unwrap();
+        let last_grandchild = Cell::from_raw(0x8459967ffffffff).unwrap();
+        let sibling = Cell::from_raw(0x82599ffffffffff).unwrap();
+        assert!(first_child < last_grandchild);
+        assert!(last_grandchild < last_child);
+        assert!(last_child < parent);
+        assert!(parent < sibling);
+        // Plain integer order puts coarser cells first instead.
+        assert!(parent.into_raw() < first_child.into_raw());
+
+        let mut cells = vec![sibling, parent, last_grandchild, last_child, first_child];
+        cells.sort();
+        assert_eq!(
+            cells,
+            vec![first_child, last_grandchild, last_child, parent, sibling]
+        );
+    }
 }
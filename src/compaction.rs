@@ -3,8 +3,31 @@
 //! Compaction allows the tree to automatically coalesce child cells into
 //! their parent when certain conditions are met, reducing memory usage
 //! and improving query performance.
+//!
+//! Most compactors only need the values of a node's children and
+//! implement [`Compactor`]. Compactors which also need to know about
+//! children that are not leaves, or the resolution being compacted
+//! into, implement [`ContextCompactor`] instead. Every `Compactor` is
+//! also a `ContextCompactor`, which is what the tree actually calls.
+//!
+//! Besides the exact [`SetCompactor`] and [`EqCompactor`], numeric
+//! maps can be coalesced into an aggregate of their children with
+//! [`Sum`], [`Mean`], [`Max`], or [`Min`], optionally only when the
+//! children are close enough according to a [`Tolerance`]. These
+//! trade precision for size; see each compactor for how.
+//!
+//! [`MinRes`] keeps any compactor from coalescing into cells coarser
+//! than a given resolution. There is no maximum-resolution
+//! counterpart. A wrapper can only veto the compactor it wraps, and
+//! vetoing coalescing into cells finer than a bound just leaves cells
+//! inserted finer than it as they are. Capping a map's resolution
+//! would instead mean coalescing every node finer than the bound
+//! whatever its children hold, discarding all but one value. To cap a
+//! map's resolution, insert [`Cell::to_parent`] of each finer cell
+//! instead.
 
-use crate::Cell;
+use crate::{cell::K_AXIS_DIGIT, node::Node, Cell};
+use std::ops::{Add, Div};
 
 /// A user-provided compactor.
 ///
@@ -16,7 +39,192 @@ pub trait Compactor<V> {
     /// Given an intermediate (non-leaf) node's cell and up to 7
     /// children, you can choose to leave the node alone by returning
     /// `None`, or turn it into a leaf node by returning `Some(value)`.
+    ///
+    /// If `cell` is a [pentagon][Cell::is_pentagon], its k-axis child,
+    /// `children[1]`, does not exist and is always `None`.
+    ///
+    /// This is not called while any child has descendants of its
+    /// own; implement [`ContextCompactor`] to handle those too.
     fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V>;
+
+    // Called by the blanket `ContextCompactor` impl. Only `MinRes`
+    // overrides it, to forward the full context to the compactor it
+    // wraps: implementing `ContextCompactor` for `MinRes` directly
+    // would conflict with that blanket impl. It isn't part of the
+    // public API, so other compactors should leave it alone.
+    #[doc(hidden)]
+    fn compact_context(&mut self, ctx: &CompactionContext<'_, V>) -> Option<V> {
+        if ctx.has_parent() {
+            return None;
+        }
+        self.compact(ctx.cell(), ctx.values())
+    }
+}
+
+/// A compactor which is given a [`CompactionContext`] describing
+/// every child of the node being compacted.
+///
+/// This is implemented for every [`Compactor`], which is only called
+/// when all of a node's children are leaves or absent. A
+/// `ContextCompactor` is called after every insert into a non-leaf
+/// node regardless, so returning `Some` when a child is
+/// [`ChildSlot::Parent`] replaces that child's entire subtree.
+///
+/// # Examples
+///
+/// Coalescing any node whose present children are all leaves, but
+/// never into cells coarser than resolution 5:
+///
+/// ```
+/// # fn main() -> hextree::Result<()> {
+/// use hextree::{
+///     compaction::{ChildSlot, CompactionContext, ContextCompactor},
+///     Cell, HexTreeMap,
+/// };
+///
+/// struct AnyLeavesAbove5;
+///
+/// impl ContextCompactor<u32> for AnyLeavesAbove5 {
+///     fn compact_with_context(&mut self, ctx: &CompactionContext<'_, u32>) -> Option<u32> {
+///         if ctx.res() < 5 || ctx.has_parent() {
+///             return None;
+///         }
+///         ctx.children()
+///             .iter()
+///             .filter_map(|child| match child {
+///                 ChildSlot::Leaf(value) => Some(**value),
+///                 _ => None,
+///             })
+///             .max()
+///     }
+/// }
+///
+/// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+/// let mut map = HexTreeMap::with_compactor(AnyLeavesAbove5);
+/// map.insert(eiffel_tower_res12, 12);
+/// assert_eq!(
+///     map.iter().collect::<Vec<_>>(),
+///     vec![(eiffel_tower_res12.to_parent(5).unwrap(), &12)]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub trait ContextCompactor<V> {
+    /// Called after every insert into a non-leaf node.
+    ///
+    /// Given the node's context, you can choose to leave the node
+    /// alone by returning `None`, or turn it into a leaf node by
+    /// returning `Some(value)`.
+    fn compact_with_context(&mut self, ctx: &CompactionContext<'_, V>) -> Option<V>;
+}
+
+impl<V, C: Compactor<V>> ContextCompactor<V> for C {
+    fn compact_with_context(&mut self, ctx: &CompactionContext<'_, V>) -> Option<V> {
+        self.compact_context(ctx)
+    }
+}
+
+/// One of the 7 child slots of a node being compacted.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChildSlot<'a, V> {
+    /// The k-axis child of a [pentagon][Cell::is_pentagon], which
+    /// does not exist.
+    Deleted,
+    /// Nothing is stored for this child.
+    Empty,
+    /// This child is a leaf with the contained value.
+    Leaf(&'a V),
+    /// This child is a parent with descendants of its own.
+    ///
+    /// How deep they go isn't given: finding out means walking the
+    /// child's whole subtree, and nodes are compacted along the path
+    /// of every insert. Nor does the tree record the resolutions cells
+    /// were inserted at before being compacted, so a leaf's resolution
+    /// is always one finer than [`CompactionContext::res`].
+    Parent,
+}
+
+impl<'a, V> Clone for ChildSlot<'a, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, V> Copy for ChildSlot<'a, V> {}
+
+/// Describes a node being compacted and its 7 child slots.
+///
+/// Passed to [`ContextCompactor::compact_with_context`].
+#[derive(Debug)]
+pub struct CompactionContext<'a, V> {
+    cell: Cell,
+    children: [ChildSlot<'a, V>; 7],
+}
+
+impl<'a, V> CompactionContext<'a, V> {
+    pub(crate) fn new(cell: Cell, nodes: &'a [Option<Box<Node<V>>>; 7]) -> Self {
+        let pentagon = cell.is_pentagon();
+        let mut children = [ChildSlot::Empty; 7];
+        for (digit, (child, node)) in children.iter_mut().zip(nodes.iter()).enumerate() {
+            *child = match node.as_deref() {
+                _ if pentagon && digit == K_AXIS_DIGIT as usize => ChildSlot::Deleted,
+                None => ChildSlot::Empty,
+                Some(Node::Leaf(value)) => ChildSlot::Leaf(value),
+                Some(Node::Parent(_)) => ChildSlot::Parent,
+            };
+        }
+        Self { cell, children }
+    }
+
+    /// Returns the context of a node whose children are all leaves or
+    /// absent, as given to [`Compactor::compact`].
+    fn from_values(cell: Cell, values: [Option<&'a V>; 7]) -> Self {
+        let pentagon = cell.is_pentagon();
+        let mut children = [ChildSlot::Empty; 7];
+        for (digit, (child, value)) in children.iter_mut().zip(values.iter()).enumerate() {
+            *child = match value {
+                _ if pentagon && digit == K_AXIS_DIGIT as usize => ChildSlot::Deleted,
+                None => ChildSlot::Empty,
+                Some(value) => ChildSlot::Leaf(*value),
+            };
+        }
+        Self { cell, children }
+    }
+
+    /// Returns the cell being compacted.
+    pub fn cell(&self) -> Cell {
+        self.cell
+    }
+
+    /// Returns the resolution of the cell being compacted, which is
+    /// one coarser than its children.
+    pub fn res(&self) -> u8 {
+        self.cell.res()
+    }
+
+    /// Returns the child slots, indexed by digit.
+    pub fn children(&self) -> &[ChildSlot<'a, V>; 7] {
+        &self.children
+    }
+
+    /// Returns `true` if any child has descendants of its own.
+    pub fn has_parent(&self) -> bool {
+        self.children
+            .iter()
+            .any(|child| matches!(child, ChildSlot::Parent))
+    }
+
+    /// Returns the values of the children which are leaves, in the
+    /// form taken by [`Compactor::compact`].
+    pub fn values(&self) -> [Option<&'a V>; 7] {
+        let mut values = [None; 7];
+        for (value, child) in values.iter_mut().zip(self.children.iter()) {
+            if let ChildSlot::Leaf(leaf) = child {
+                *value = Some(*leaf);
+            }
+        }
+        values
+    }
 }
 
 /// A compactor that performs no compaction.
@@ -42,8 +250,8 @@ impl<V> Compactor<V> for NullCompactor {
 pub struct SetCompactor;
 
 impl Compactor<()> for SetCompactor {
-    fn compact(&mut self, _cell: Cell, children: [Option<&()>; 7]) -> Option<()> {
-        if children.iter().all(Option::is_some) {
+    fn compact(&mut self, cell: Cell, children: [Option<&()>; 7]) -> Option<()> {
+        if existing_children(cell, &children).all(|child| child.is_some()) {
             Some(())
         } else {
             None
@@ -61,12 +269,561 @@ impl Compactor<()> for SetCompactor {
 pub struct EqCompactor;
 
 impl<V: PartialEq + Clone> Compactor<V> for EqCompactor {
-    fn compact(&mut self, _cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
-        if let [Some(v0), Some(v1), Some(v2), Some(v3), Some(v4), Some(v5), Some(v6)] = children {
-            if v0 == v1 && v1 == v2 && v2 == v3 && v3 == v4 && v4 == v5 && v5 == v6 {
-                return Some(v0.clone());
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        let mut children = existing_children(cell, &children);
+        let first = children.next().flatten()?;
+        if children.all(|child| child == Some(first)) {
+            Some(first.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// A compactor that coalesces sets when at least `N` of a node's
+/// children are present.
+///
+/// This is lossy: coalescing a node with missing children adds the
+/// missing cells to the set. It is useful for smoothing over small
+/// gaps in coverage. Pentagons have only 6 children, so they are
+/// coalesced with at least `min(N, 6)`.
+///
+/// Since a coalesced node counts as a present child of its own
+/// parent, small values of `N` can cascade all the way up to the
+/// base cell. Wrap this in [`MinRes`] to bound how coarse the
+/// resulting cells get.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> hextree::Result<()> {
+/// use hextree::{
+///     compaction::{MinRes, Threshold},
+///     Cell, HexTreeMap,
+/// };
+///
+/// let parent = Cell::from_raw(0x825997fffffffff)?;
+/// let mut set = HexTreeMap::with_compactor(MinRes(2, Threshold(6)));
+/// for cell in parent.children(3).skip(1) {
+///     set.insert(cell, ());
+/// }
+/// assert_eq!(set.iter().collect::<Vec<_>>(), vec![(parent, &())]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threshold(pub u8);
+
+impl Compactor<()> for Threshold {
+    fn compact(&mut self, cell: Cell, children: [Option<&()>; 7]) -> Option<()> {
+        let (present, existing) = existing_children(cell, &children)
+            .fold((0, 0), |(present, existing), child| {
+                (present + child.is_some() as u8, existing + 1)
+            });
+        if present >= self.0.min(existing) {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/// A compactor that only lets the wrapped compactor coalesce into
+/// cells at resolution `res` or finer.
+///
+/// Use this when consumers of a map can't handle cells coarser than
+/// `res`, but still benefit from compaction below it. Any
+/// [`ContextCompactor`], and so any [`Compactor`], can be wrapped.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> hextree::Result<()> {
+/// use hextree::{
+///     compaction::{EqCompactor, MinRes},
+///     Cell, HexTreeMap,
+/// };
+///
+/// let parent = Cell::from_raw(0x825997fffffffff)?;
+/// let mut map = HexTreeMap::with_compactor(MinRes(3, EqCompactor));
+/// for cell in parent.children(4) {
+///     map.insert(cell, "US915");
+/// }
+/// // The res-4 cells coalesce into their res-3 parents, but no
+/// // further.
+/// assert_eq!(map.len(), 7);
+/// assert!(map.iter().all(|(cell, _)| cell.res() == 3));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinRes<C>(pub u8, pub C);
+
+impl<V, C: ContextCompactor<V>> Compactor<V> for MinRes<C> {
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        self.compact_context(&CompactionContext::from_values(cell, children))
+    }
+
+    fn compact_context(&mut self, ctx: &CompactionContext<'_, V>) -> Option<V> {
+        if ctx.res() < self.0 {
+            return None;
+        }
+        self.1.compact_with_context(ctx)
+    }
+}
+
+/// A compactor that coalesces nodes when all of their children are
+/// present and pairwise within tolerance, delegating the parent's
+/// value to the wrapped aggregate.
+///
+/// The tolerance closure is called with every pair of present
+/// children and returns whether they are close enough to merge.
+///
+/// # Precision
+///
+/// Tolerance is only checked between siblings, and a coalesced
+/// parent's value is an aggregate rather than any of the values
+/// inserted. That parent can then be within tolerance of its own
+/// siblings and coalesce again, so the value finally stored for a
+/// region can drift from an inserted value by up to the tolerance at
+/// every resolution it is coalesced through. Bound this with
+/// [`MinRes`], or use a tighter tolerance, if that matters.
+///
+/// # Examples
+///
+/// Merging RSSI readings within 2 dBm of each other into their mean:
+///
+/// ```
+/// # fn main() -> hextree::Result<()> {
+/// use hextree::{
+///     compaction::{Mean, Tolerance},
+///     Cell, HexTreeMap,
+/// };
+///
+/// let parent = Cell::from_raw(0x825997fffffffff)?;
+/// let mut map = HexTreeMap::with_compactor(Tolerance(
+///     |a: &f32, b: &f32| (a - b).abs() <= 2.0,
+///     Mean,
+/// ));
+/// let rssis = [-80.0, -81.0, -79.0, -80.0, -80.5, -79.5, -80.0];
+/// for (cell, rssi) in parent.children(3).zip(rssis) {
+///     map.insert(cell, rssi);
+/// }
+/// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(parent, &-80.0)]);
+///
+/// // A reading that's too far off leaves its siblings alone.
+/// let mut map = HexTreeMap::with_compactor(Tolerance(
+///     |a: &f32, b: &f32| (a - b).abs() <= 2.0,
+///     Mean,
+/// ));
+/// let rssis = [-80.0, -81.0, -79.0, -80.0, -80.5, -79.5, -90.0];
+/// for (cell, rssi) in parent.children(3).zip(rssis) {
+///     map.insert(cell, rssi);
+/// }
+/// assert_eq!(map.len(), 7);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Tolerance<F, A>(pub F, pub A);
+
+impl<V, F, A> Compactor<V> for Tolerance<F, A>
+where
+    F: FnMut(&V, &V) -> bool,
+    A: Compactor<V>,
+{
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        let present = || children.iter().flatten();
+        for (i, a) in present().enumerate() {
+            if !present().skip(i + 1).all(|b| (self.0)(a, b)) {
+                return None;
             }
-        };
+        }
+        self.1.compact(cell, children)
+    }
+}
+
+/// A compactor that coalesces nodes when all of their children are
+/// present, into the sum of their values.
+///
+/// Use this for additive values, like counts, where a parent should
+/// hold the total of the region it covers.
+///
+/// # Splitting
+///
+/// Operations which split a coalesced parent back into its children,
+/// like [`HexTreeMap::remove`], [`InsertPolicy::Split`], and
+/// [`HexTreeMap::merge`], copy the parent's value into every child
+/// rather than dividing it between them. Once coalesced again, the
+/// parent holds 7 times its previous total, or 6 times for a
+/// pentagon. Avoid those operations on summed maps, or rebuild the
+/// affected region from its original counts.
+///
+/// [`HexTreeMap::remove`]: crate::HexTreeMap::remove
+/// [`HexTreeMap::merge`]: crate::HexTreeMap::merge
+/// [`InsertPolicy::Split`]: crate::hex_tree_map::InsertPolicy::Split
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sum;
+
+impl<V: Clone + Add<Output = V>> Compactor<V> for Sum {
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        let mut values = full_children(cell, &children)?;
+        let first = values.next()?.clone();
+        Some(values.fold(first, |sum, value| sum + value.clone()))
+    }
+}
+
+/// A compactor that coalesces nodes when all of their children are
+/// present, into the mean of their values.
+///
+/// This is implemented for `f32` and `f64`. Integer means would need
+/// a wider type to sum into and truncate at every resolution, so
+/// convert integer readings to floating point first.
+///
+/// # Precision
+///
+/// Every child is weighted equally, whether it's a cell as inserted
+/// or a parent already coalesced from its own children. A parent only
+/// holds the mean of the finest cells beneath it when every cell
+/// between them has the same number of children, so cells under a
+/// pentagon, which has 6, or under a child inserted at a coarser
+/// resolution than its siblings' descendants, are weighted more than
+/// the rest. H3 cells are also only approximately equal-area, so this
+/// is not exactly an area-weighted mean, and each coalesce adds a
+/// little floating point rounding error. Use [`Tolerance`] to avoid
+/// averaging away large differences.
+///
+/// Splitting a coalesced parent, as described for [`Sum`], copies
+/// its mean into every child, so the finer values it was computed
+/// from are not recovered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mean;
+
+impl Compactor<f32> for Mean {
+    fn compact(&mut self, cell: Cell, children: [Option<&f32>; 7]) -> Option<f32> {
+        mean(cell, &children)
+    }
+}
+
+impl Compactor<f64> for Mean {
+    fn compact(&mut self, cell: Cell, children: [Option<&f64>; 7]) -> Option<f64> {
+        mean(cell, &children)
+    }
+}
+
+/// A compactor that coalesces nodes when all of their children are
+/// present, into the largest of their values.
+///
+/// Values which are unordered with respect to the others, like a
+/// floating point NaN, are only kept if they come first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Max;
+
+impl<V: Clone + PartialOrd> Compactor<V> for Max {
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        let mut values = full_children(cell, &children)?;
+        let first = values.next()?;
+        Some(
+            values
+                .fold(first, |max, value| if value > max { value } else { max })
+                .clone(),
+        )
+    }
+}
+
+/// A compactor that coalesces nodes when all of their children are
+/// present, into the smallest of their values.
+///
+/// Values which are unordered with respect to the others, like a
+/// floating point NaN, are only kept if they come first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Min;
+
+impl<V: Clone + PartialOrd> Compactor<V> for Min {
+    fn compact(&mut self, cell: Cell, children: [Option<&V>; 7]) -> Option<V> {
+        let mut values = full_children(cell, &children)?;
+        let first = values.next()?;
+        Some(
+            values
+                .fold(first, |min, value| if value < min { value } else { min })
+                .clone(),
+        )
+    }
+}
+
+/// Returns the mean of `cell`'s children if every one that exists is
+/// present.
+fn mean<V>(cell: Cell, children: &[Option<&V>; 7]) -> Option<V>
+where
+    V: Copy + Add<Output = V> + Div<Output = V> + From<u8>,
+{
+    let (sum, count) = full_children(cell, children)?
+        .fold((V::from(0), 0), |(sum, count), &value| {
+            (sum + value, count + 1)
+        });
+    Some(sum / V::from(count))
+}
+
+/// Returns an iterator over the values of `cell`'s children if every
+/// one that exists is present.
+fn full_children<'a, V>(
+    cell: Cell,
+    children: &'a [Option<&'a V>; 7],
+) -> Option<impl Iterator<Item = &'a V> + 'a> {
+    if existing_children(cell, children).all(|child| child.is_some()) {
+        Some(existing_children(cell, children).flatten())
+    } else {
         None
     }
 }
+
+/// Returns an iterator over `cell`'s child slots, skipping the deleted
+/// k-axis slot if `cell` is a pentagon.
+fn existing_children<'a, V>(
+    cell: Cell,
+    children: &'a [Option<&'a V>; 7],
+) -> impl Iterator<Item = Option<&'a V>> + 'a {
+    let pentagon = cell.is_pentagon();
+    children
+        .iter()
+        .enumerate()
+        .filter(move |(digit, _)| !(pentagon && *digit == K_AXIS_DIGIT as usize))
+        .map(|(_, child)| *child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HexTreeMap;
+
+    #[test]
+    fn test_set_compactor_pentagon() {
+        // Base cell 4 is a pentagon.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let mut set = HexTreeMap::with_compactor(SetCompactor);
+        for child in pentagon.children(2) {
+            set.insert(child, ());
+        }
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(pentagon), Some((pentagon, &())));
+    }
+
+    #[test]
+    fn test_eq_compactor_pentagon() {
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        for child in pentagon.children(1) {
+            map.insert(child, "pentagon");
+        }
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(pentagon), Some((pentagon, &"pentagon")));
+
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        for (i, child) in pentagon.children(1).enumerate() {
+            map.insert(child, i);
+        }
+        assert_eq!(map.len(), 6);
+    }
+
+    #[test]
+    fn test_compaction_context() {
+        // Records the slots of every context it's given, never
+        // compacting.
+        type Calls = std::rc::Rc<std::cell::RefCell<Vec<(Cell, Vec<ChildSlot<'static, ()>>)>>>;
+        struct Recorder(Calls);
+
+        impl ContextCompactor<()> for Recorder {
+            fn compact_with_context(&mut self, ctx: &CompactionContext<'_, ()>) -> Option<()> {
+                let slots = ctx
+                    .children()
+                    .iter()
+                    .map(|child| match child {
+                        ChildSlot::Deleted => ChildSlot::Deleted,
+                        ChildSlot::Empty => ChildSlot::Empty,
+                        ChildSlot::Leaf(_) => ChildSlot::Leaf(&()),
+                        ChildSlot::Parent => ChildSlot::Parent,
+                    })
+                    .collect();
+                self.0.borrow_mut().push((ctx.cell(), slots));
+                None
+            }
+        }
+
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let center = pentagon.center_child(1).unwrap();
+        let calls = Calls::default();
+        let mut map = HexTreeMap::with_compactor(Recorder(calls.clone()));
+        map.insert(center.center_child(2).unwrap(), ());
+        map.insert(Cell::from_raw(0x8108bffffffffff).unwrap(), ());
+
+        let calls = calls.borrow();
+        let (cell, slots) = calls.last().unwrap();
+        assert_eq!(*cell, pentagon);
+        assert_eq!(
+            slots,
+            &[
+                ChildSlot::Parent,
+                ChildSlot::Deleted,
+                ChildSlot::Leaf(&()),
+                ChildSlot::Empty,
+                ChildSlot::Empty,
+                ChildSlot::Empty,
+                ChildSlot::Empty,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blanket_context_compactor() {
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let children = [
+            Some(Box::new(Node::Leaf(1))),
+            None,
+            Some(Box::new(Node::Leaf(1))),
+            Some(Box::new(Node::Leaf(1))),
+            Some(Box::new(Node::Leaf(1))),
+            Some(Box::new(Node::Leaf(1))),
+            Some(Box::new(Node::Leaf(1))),
+        ];
+        let ctx = CompactionContext::new(pentagon, &children);
+        assert_eq!(ctx.res(), 0);
+        assert_eq!(ctx.children()[1], ChildSlot::Deleted);
+        assert!(!ctx.has_parent());
+        assert_eq!(EqCompactor.compact_with_context(&ctx), Some(1));
+
+        // A `Compactor` is never asked to compact over a parent.
+        let mut children = children;
+        children[0] = Some(Box::new(Node::new()));
+        let ctx = CompactionContext::new(pentagon, &children);
+        assert!(ctx.has_parent());
+        assert_eq!(ctx.values()[0], None);
+        assert_eq!(EqCompactor.compact_with_context(&ctx), None);
+    }
+
+    #[test]
+    fn test_threshold_compactor() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let mut set = HexTreeMap::with_compactor(MinRes(2, Threshold(5)));
+        for cell in parent.children(3).take(4) {
+            set.insert(cell, ());
+        }
+        assert_eq!(set.len(), 4);
+        set.insert(parent.children(3).nth(5).unwrap(), ());
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![(parent, &())]);
+
+        // Pentagons have only 6 children to count.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let mut set = HexTreeMap::with_compactor(MinRes(0, Threshold(7)));
+        for child in pentagon.children(1) {
+            set.insert(child, ());
+        }
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![(pentagon, &())]);
+
+        // Without a bound, a low threshold cascades up to the base
+        // cell.
+        let mut set = HexTreeMap::with_compactor(Threshold(1));
+        set.insert(parent.center_child(10).unwrap(), ());
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![(parent.to_parent(0).unwrap(), &())]
+        );
+    }
+
+    #[test]
+    fn test_min_res_compactor() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let grandparent = parent.to_parent(1).unwrap();
+
+        let mut map = HexTreeMap::with_compactor(MinRes(2, EqCompactor));
+        for cell in grandparent.children(4) {
+            map.insert(cell, 1);
+        }
+        assert_eq!(map.len(), 7);
+        assert!(map.iter().all(|(cell, _)| cell.res() == 2));
+
+        // Context compactors can be wrapped too, including ones that
+        // coalesce over parents.
+        struct Any;
+
+        impl ContextCompactor<u32> for Any {
+            fn compact_with_context(&mut self, _ctx: &CompactionContext<'_, u32>) -> Option<u32> {
+                Some(0)
+            }
+        }
+
+        let mut map = HexTreeMap::with_compactor(MinRes(2, Any));
+        map.insert(parent.center_child(5).unwrap(), 1);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(parent, &0)]);
+    }
+
+    #[test]
+    fn test_aggregate_compactors() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let grandparent = parent.to_parent(1).unwrap();
+
+        let mut sum = HexTreeMap::with_compactor(Sum);
+        let mut mean = HexTreeMap::with_compactor(Mean);
+        let mut max = HexTreeMap::with_compactor(Max);
+        let mut min = HexTreeMap::with_compactor(Min);
+        for (i, cell) in grandparent.children(3).enumerate() {
+            sum.insert(cell, 1u32);
+            mean.insert(cell, i as f64);
+            max.insert(cell, i);
+            min.insert(cell, i);
+        }
+        assert_eq!(sum.iter().collect::<Vec<_>>(), vec![(grandparent, &49)]);
+        assert_eq!(mean.iter().collect::<Vec<_>>(), vec![(grandparent, &24.0)]);
+        assert_eq!(max.iter().collect::<Vec<_>>(), vec![(grandparent, &48)]);
+        assert_eq!(min.iter().collect::<Vec<_>>(), vec![(grandparent, &0)]);
+
+        // Nothing is coalesced while a child is missing.
+        let mut sum = HexTreeMap::with_compactor(Sum);
+        for cell in parent.children(3).skip(1) {
+            sum.insert(cell, 1u32);
+        }
+        assert_eq!(sum.len(), 6);
+
+        // Pentagons are coalesced with their 6 children.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let mut mean = HexTreeMap::with_compactor(Mean);
+        for (i, child) in pentagon.children(1).enumerate() {
+            mean.insert(child, i as f32 * 2.0);
+        }
+        assert_eq!(mean.iter().collect::<Vec<_>>(), vec![(pentagon, &5.0)]);
+    }
+
+    #[test]
+    fn test_tolerance_compactor() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let within = |tolerance: i32| move |a: &i32, b: &i32| (a - b).abs() <= tolerance;
+
+        let mut map = HexTreeMap::with_compactor(Tolerance(within(3), Max));
+        for (i, cell) in parent.children(3).enumerate() {
+            map.insert(cell, i as i32);
+        }
+        assert_eq!(map.len(), 7);
+
+        let mut map = HexTreeMap::with_compactor(Tolerance(within(6), Max));
+        for (i, cell) in parent.children(3).enumerate() {
+            map.insert(cell, i as i32);
+        }
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(parent, &6)]);
+
+        // Coalesced values drift by up to the tolerance per resolution.
+        let grandparent = parent.to_parent(1).unwrap();
+        let mut map = HexTreeMap::with_compactor(Tolerance(within(1), Max));
+        for (i, cell) in parent.children(3).enumerate() {
+            map.insert(cell, i.min(1) as i32);
+        }
+        for sibling in grandparent.children(2).filter(|&cell| cell != parent) {
+            map.insert(sibling, 2);
+        }
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(grandparent, &2)]);
+    }
+}
@@ -140,7 +140,7 @@ mod tests {
         let monaco_hextree: HexTreeMap<&[u8]> = {
             let mut map = HexTreeMap::new();
             for (cell, val) in &monaco_hashmap {
-                map.insert(**cell, val.as_slice())
+                map.insert(**cell, val.as_slice());
             }
             map
         };
@@ -1,5 +1,5 @@
 use crate::{
-    compaction::Compactor,
+    compaction::ContextCompactor,
     disktree::{dptr::Dp, dtseek::DtSeek, tree::HDR_MAGIC, varint},
     error::{Error, Result},
     node::Node,
@@ -10,7 +10,7 @@ use std::io::Write;
 
 impl<V, C> HexTreeMap<V, C>
 where
-    C: Compactor<V>,
+    C: ContextCompactor<V>,
 {
     /// Write self to disk.
     pub fn to_disktree<W, F, E>(&self, wtr: W, f: F) -> Result
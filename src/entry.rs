@@ -1,6 +1,6 @@
 //! `HexTreeMap`'s Entry API.
 
-use crate::{compaction::Compactor, Cell, HexTreeMap};
+use crate::{compaction::ContextCompactor, Cell, HexTreeMap};
 
 /// A view into a single entry in a map, which may either be vacant or
 /// occupied.
@@ -30,7 +30,7 @@ pub struct VacantEntry<'a, V, C> {
 
 impl<'a, V, C> Entry<'a, V, C>
 where
-    C: Compactor<V>,
+    C: ContextCompactor<V>,
 {
     /// Provides in-place mutable access to an occupied entry before
     /// any potential inserts into the map.
@@ -148,7 +148,7 @@ where
 impl<'a, V, C> Entry<'a, V, C>
 where
     V: Default,
-    C: Compactor<V>,
+    C: ContextCompactor<V>,
 {
     /// Ensures a value is in the entry by inserting the default value
     /// if empty, and returns a mutable reference to the value in the
@@ -1,3 +1,5 @@
+use crate::Cell;
+
 /// Result type for this crate
 pub type Result<T = ()> = std::result::Result<T, Error>;
 
@@ -8,6 +10,31 @@ pub enum Error {
     /// An invalid raw source value was used for an H3 cell.
     Index(u64),
 
+    /// A raw H3 index failed [strict validation], along with the
+    /// check which failed.
+    ///
+    /// [strict validation]: crate::Cell::from_raw_strict
+    InvalidCell(u64, InvalidCell),
+
+    /// A string is not a hexadecimal H3 index.
+    ParseCell(String),
+
+    /// A cell could not be inserted because a coarser leaf, the
+    /// second cell, already covers it.
+    Shadowed(Cell, Cell),
+
+    /// A latitude or longitude is not finite.
+    #[cfg(feature = "geo")]
+    LatLng(f64, f64),
+
+    /// A resolution is greater than 15.
+    #[cfg(feature = "geo")]
+    Resolution(u8),
+
+    /// GeoJSON could not be read, or has unsupported geometry.
+    #[cfg(feature = "geojson")]
+    GeoJson(Box<geojson::Error>),
+
     /// An io error.
     #[cfg(feature = "disktree")]
     Io(std::io::Error),
@@ -33,6 +60,52 @@ pub enum Error {
     Writer(Box<dyn std::error::Error + Send + Sync>),
 }
 
+/// The reason a raw H3 index is not a valid cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidCell {
+    /// The reserved high bit is set.
+    Reserved,
+
+    /// The mode is not 1 (cell), got the contained mode.
+    Mode(u8),
+
+    /// The mode-dependent bits are not 0, got the contained bits.
+    ModeDependent(u8),
+
+    /// The base cell is not in `[0, 122)`, got the contained base.
+    Base(u8),
+
+    /// The digit at the contained resolution, which is at or above
+    /// the cell's resolution, is 7.
+    Digit(u8),
+
+    /// The digit at the contained resolution, which is below the
+    /// cell's resolution, is not 7.
+    UnusedDigit(u8),
+
+    /// The cell descends from a pentagon along its deleted k-axis.
+    DeletedSubsequence,
+}
+
+impl std::fmt::Display for InvalidCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InvalidCell::Reserved => write!(f, "reserved bit is set"),
+            InvalidCell::Mode(mode) => write!(f, "mode is not 1, got {mode}"),
+            InvalidCell::ModeDependent(bits) => {
+                write!(f, "mode-dependent bits are not 0, got {bits}")
+            }
+            InvalidCell::Base(base) => write!(f, "base cell is not in [0,122), got {base}"),
+            InvalidCell::Digit(res) => write!(f, "digit at res {res} is 7"),
+            InvalidCell::UnusedDigit(res) => write!(f, "unused digit at res {res} is not 7"),
+            InvalidCell::DeletedSubsequence => {
+                write!(f, "cell is in a pentagon's deleted k-axis subsequence")
+            }
+        }
+    }
+}
+
 #[cfg(feature = "disktree")]
 impl std::convert::From<std::io::Error> for Error {
     fn from(other: std::io::Error) -> Self {
@@ -40,11 +113,33 @@ impl std::convert::From<std::io::Error> for Error {
     }
 }
 
+#[cfg(feature = "geojson")]
+impl std::convert::From<geojson::Error> for Error {
+    fn from(other: geojson::Error) -> Self {
+        Error::GeoJson(Box::new(other))
+    }
+}
+
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Error::Index(_) => None,
 
+            Error::InvalidCell(_, _) => None,
+
+            Error::ParseCell(_) => None,
+
+            Error::Shadowed(_, _) => None,
+
+            #[cfg(feature = "geo")]
+            Error::LatLng(_, _) => None,
+
+            #[cfg(feature = "geo")]
+            Error::Resolution(_) => None,
+
+            #[cfg(feature = "geojson")]
+            Error::GeoJson(inner) => inner.source(),
+
             #[cfg(feature = "disktree")]
             Error::Io(inner) => inner.source(),
 
@@ -71,6 +166,33 @@ impl std::fmt::Display for Error {
         match self {
             Error::Index(bits) => write!(f, "raw u64 is not a valid H3 index: {bits}"),
 
+            Error::InvalidCell(bits, reason) => {
+                write!(f, "raw u64 is not a valid H3 cell: {bits}, {reason}")
+            }
+
+            Error::ParseCell(string) => {
+                write!(f, "string is not a hexadecimal H3 index: {string:?}")
+            }
+
+            Error::Shadowed(cell, ancestor) => {
+                write!(f, "cell {cell} is already covered by leaf {ancestor}")
+            }
+
+            #[cfg(feature = "geo")]
+            Error::LatLng(lat, lng) => {
+                write!(f, "lat/lng is not finite, got ({lat}, {lng})")
+            }
+
+            #[cfg(feature = "geo")]
+            Error::Resolution(res) => {
+                write!(f, "resolution is not in [0, 15], got {res}")
+            }
+
+            #[cfg(feature = "geojson")]
+            Error::GeoJson(geojson_error) => {
+                write!(f, "invalid GeoJSON, got {geojson_error}")
+            }
+
             #[cfg(feature = "disktree")]
             Error::Io(io_error) => io_error.fmt(f),
 
@@ -0,0 +1,309 @@
+//! Coordinate systems used to project between lat/lng and H3 cells.
+//!
+//! H3 centers an `ijk` hex coordinate system on each face of an
+//! icosahedron. Each resolution is rotated ~19.1° relative to the
+//! next coarser one, alternating counterclockwise and clockwise, so
+//! even resolutions are "Class II" and odd ones "Class III".
+
+use std::ops::{Add, Mul, Sub};
+
+/// `sin(60°)`.
+pub(crate) const SQRT3_2: f64 = 0.866_025_403_784_438_6;
+
+/// `1 / sin(60°)`.
+const RSIN60: f64 = 1.154_700_538_379_251_5;
+
+/// Hex coordinates with three axes, 120° apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CoordIJK {
+    pub(crate) i: i32,
+    pub(crate) j: i32,
+    pub(crate) k: i32,
+}
+
+/// Unit vectors for each H3 digit, with digit 0 being the center.
+const UNIT_VECS: [CoordIJK; 7] = [
+    CoordIJK::new(0, 0, 0),
+    CoordIJK::new(0, 0, 1),
+    CoordIJK::new(0, 1, 0),
+    CoordIJK::new(0, 1, 1),
+    CoordIJK::new(1, 0, 0),
+    CoordIJK::new(1, 0, 1),
+    CoordIJK::new(1, 1, 0),
+];
+
+impl CoordIJK {
+    pub(crate) const fn new(i: i32, j: i32, k: i32) -> Self {
+        Self { i, j, k }
+    }
+
+    /// Returns the canonical form of these coordinates, where all
+    /// components are non-negative and at least one is zero.
+    pub(crate) fn normalize(self) -> Self {
+        let min = self.i.min(self.j).min(self.k);
+        Self::new(self.i - min, self.j - min, self.k - min)
+    }
+
+    /// Returns the sum of the three components.
+    pub(crate) fn sum(self) -> i32 {
+        self.i + self.j + self.k
+    }
+
+    /// Returns the number of unit steps between `self` and `other`.
+    pub(crate) fn distance(self, other: Self) -> i32 {
+        let diff = (self - other).normalize();
+        diff.i.abs().max(diff.j.abs()).max(diff.k.abs())
+    }
+
+    /// Returns the neighboring coordinates in the direction of H3
+    /// `digit`.
+    pub(crate) fn neighbor(self, digit: u8) -> Self {
+        (self + UNIT_VECS[digit as usize]).normalize()
+    }
+
+    /// Returns the H3 digit these (unit vector) coordinates point
+    /// towards, or `None` if they are not a unit vector.
+    pub(crate) fn to_digit(self) -> Option<u8> {
+        let normalized = self.normalize();
+        UNIT_VECS
+            .iter()
+            .position(|unit| *unit == normalized)
+            .map(|digit| digit as u8)
+    }
+
+    /// Returns the sum of `self`'s components scaled by the images of
+    /// the `i`, `j`, and `k` unit vectors.
+    fn transform(self, i: Self, j: Self, k: Self) -> Self {
+        (i * self.i + j * self.j + k * self.k).normalize()
+    }
+
+    /// Returns the coordinates of the parent cell containing `self`,
+    /// one counterclockwise aperture 7 resolution coarser.
+    pub(crate) fn up_ap7(self) -> Self {
+        let i = f64::from(self.i - self.k);
+        let j = f64::from(self.j - self.k);
+        Self::new(
+            ((3.0 * i - j) / 7.0).round() as i32,
+            ((i + 2.0 * j) / 7.0).round() as i32,
+            0,
+        )
+        .normalize()
+    }
+
+    /// Returns the coordinates of the parent cell containing `self`,
+    /// one clockwise aperture 7 resolution coarser.
+    pub(crate) fn up_ap7r(self) -> Self {
+        let i = f64::from(self.i - self.k);
+        let j = f64::from(self.j - self.k);
+        Self::new(
+            ((2.0 * i + j) / 7.0).round() as i32,
+            ((3.0 * j - i) / 7.0).round() as i32,
+            0,
+        )
+        .normalize()
+    }
+
+    /// Returns the coordinates of `self`'s center child, one
+    /// counterclockwise aperture 7 resolution finer.
+    pub(crate) fn down_ap7(self) -> Self {
+        self.transform(Self::new(3, 0, 1), Self::new(1, 3, 0), Self::new(0, 1, 3))
+    }
+
+    /// Returns the coordinates of `self`'s center child, one clockwise
+    /// aperture 7 resolution finer.
+    pub(crate) fn down_ap7r(self) -> Self {
+        self.transform(Self::new(3, 1, 0), Self::new(0, 3, 1), Self::new(1, 0, 3))
+    }
+
+    /// Returns the coordinates of `self`'s center on a counterclockwise
+    /// aperture 3 substrate grid.
+    pub(crate) fn down_ap3(self) -> Self {
+        self.transform(Self::new(2, 0, 1), Self::new(1, 2, 0), Self::new(0, 1, 2))
+    }
+
+    /// Returns the coordinates of `self`'s center on a clockwise
+    /// aperture 3 substrate grid.
+    pub(crate) fn down_ap3r(self) -> Self {
+        self.transform(Self::new(2, 1, 0), Self::new(0, 2, 1), Self::new(1, 0, 2))
+    }
+
+    /// Returns `self` rotated 60° counterclockwise about the origin.
+    pub(crate) fn rotate60_ccw(self) -> Self {
+        self.transform(Self::new(1, 1, 0), Self::new(0, 1, 1), Self::new(1, 0, 1))
+    }
+
+    /// Returns `self` rotated 60° clockwise about the origin.
+    pub(crate) fn rotate60_cw(self) -> Self {
+        self.transform(Self::new(1, 0, 1), Self::new(1, 1, 0), Self::new(0, 1, 1))
+    }
+
+    /// Returns the center of the hex at these coordinates in the
+    /// face's cartesian plane.
+    pub(crate) fn to_hex2d(self) -> Vec2d {
+        let i = f64::from(self.i - self.k);
+        let j = f64::from(self.j - self.k);
+        Vec2d::new(i - 0.5 * j, j * SQRT3_2)
+    }
+
+    /// Returns the coordinates of the hex containing `v`.
+    pub(crate) fn from_hex2d(v: Vec2d) -> Self {
+        let a1 = v.x.abs();
+        let a2 = v.y.abs();
+
+        // First do a reverse conversion.
+        let x2 = a2 * RSIN60;
+        let x1 = a1 + x2 / 2.0;
+
+        // Check if we have the center of a hex.
+        let m1 = x1 as i32;
+        let m2 = x2 as i32;
+
+        // Otherwise round correctly.
+        let r1 = x1 - f64::from(m1);
+        let r2 = x2 - f64::from(m2);
+
+        let (mut i, mut j) = if r1 < 0.5 {
+            if r1 < 1.0 / 3.0 {
+                (m1, if r2 < (1.0 + r1) / 2.0 { m2 } else { m2 + 1 })
+            } else {
+                let i = if (1.0 - r1) <= r2 && r2 < (2.0 * r1) {
+                    m1 + 1
+                } else {
+                    m1
+                };
+                (i, if r2 < (1.0 - r1) { m2 } else { m2 + 1 })
+            }
+        } else if r1 < 2.0 / 3.0 {
+            let j = if r2 < (1.0 - r1) { m2 } else { m2 + 1 };
+            let i = if (2.0 * r1 - 1.0) < r2 && r2 < (1.0 - r1) {
+                m1
+            } else {
+                m1 + 1
+            };
+            (i, j)
+        } else {
+            (m1 + 1, if r2 < r1 / 2.0 { m2 } else { m2 + 1 })
+        };
+
+        // Now fold across the axes if necessary.
+        if v.x < 0.0 {
+            if j % 2 == 0 {
+                let axis_i = j / 2;
+                let diff = i - axis_i;
+                i -= 2 * diff;
+            } else {
+                let axis_i = (j + 1) / 2;
+                let diff = i - axis_i;
+                i -= 2 * diff + 1;
+            }
+        }
+        if v.y < 0.0 {
+            i -= (2 * j + 1) / 2;
+            j = -j;
+        }
+
+        Self::new(i, j, 0).normalize()
+    }
+}
+
+impl Add for CoordIJK {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.i + other.i, self.j + other.j, self.k + other.k)
+    }
+}
+
+impl Sub for CoordIJK {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.i - other.i, self.j - other.j, self.k - other.k)
+    }
+}
+
+impl Mul<i32> for CoordIJK {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        Self::new(self.i * factor, self.j * factor, self.k * factor)
+    }
+}
+
+/// A point in a face's cartesian plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Vec2d {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+}
+
+impl Vec2d {
+    pub(crate) const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Returns the distance from the origin.
+    pub(crate) fn magnitude(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// Returns the point where the line through `p0` and `p1` crosses
+    /// the line through `p2` and `p3`.
+    pub(crate) fn intersect(p0: Self, p1: Self, p2: Self, p3: Self) -> Self {
+        let s1 = Self::new(p1.x - p0.x, p1.y - p0.y);
+        let s2 = Self::new(p3.x - p2.x, p3.y - p2.y);
+        let t = (s2.x * (p0.y - p2.y) - s2.y * (p0.x - p2.x)) / (-s2.x * s1.y + s1.x * s2.y);
+        Self::new(p0.x + t * s1.x, p0.y + t * s1.y)
+    }
+
+    /// Returns `true` if `self` and `other` are equal within `f32`
+    /// precision.
+    pub(crate) fn almost_eq(self, other: Self) -> bool {
+        (self.x - other.x).abs() < f64::from(f32::EPSILON)
+            && (self.y - other.y).abs() < f64::from(f32::EPSILON)
+    }
+}
+
+/// `ijk` coordinates on a specific icosahedron face.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FaceIJK {
+    pub(crate) face: u8,
+    pub(crate) coord: CoordIJK,
+}
+
+impl FaceIJK {
+    pub(crate) const fn new(face: u8, coord: CoordIJK) -> Self {
+        Self { face, coord }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex2d_roundtrip() {
+        for i in 0..10 {
+            for j in 0..10 {
+                for k in 0..10 {
+                    let ijk = CoordIJK::new(i, j, k).normalize();
+                    assert_eq!(CoordIJK::from_hex2d(ijk.to_hex2d()), ijk);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aperture_roundtrip() {
+        let ijk = CoordIJK::new(3, 0, 5).normalize();
+        assert_eq!(ijk.down_ap7().up_ap7(), ijk);
+        assert_eq!(ijk.down_ap7r().up_ap7r(), ijk);
+        assert_eq!(ijk.rotate60_ccw().rotate60_cw(), ijk);
+        // Aperture 3 twice, alternating orientation, is a plain scale.
+        assert_eq!(ijk.down_ap3().down_ap3r(), (ijk * 3).normalize());
+        for digit in 0..7 {
+            assert_eq!(CoordIJK::default().neighbor(digit).to_digit(), Some(digit));
+        }
+        assert_eq!(CoordIJK::new(2, 0, 0).to_digit(), None);
+    }
+}
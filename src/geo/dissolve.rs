@@ -0,0 +1,361 @@
+//! Dissolving a set of cells into the polygons outlining it.
+//!
+//! Cells at different resolutions don't share edges: a cell's
+//! children stick out past its own boundary in some places and fall
+//! short of it in others. So the outline is traced along the edges of
+//! the set's finest resolution, descending from each leaf only into
+//! children which touch an uncovered cell. The interior of a compacted
+//! set is never expanded, which keeps the work proportional to the
+//! length of the outline rather than the area of the set.
+
+use super::{index_to_boundary, index_to_latlng, ring_contains, unwrap_lng};
+use crate::{cell::Index, Cell};
+use geo_types::{Coord, LineString, MultiPolygon, Polygon};
+use std::collections::{BTreeMap, HashMap};
+
+/// Size of the buckets corners are matched in, in unit sphere
+/// distances.
+///
+/// This is far smaller than the distance between any two corners,
+/// even at resolution 15, yet far larger than the rounding error
+/// between one corner computed from each of its cells.
+const CORNER_GRID: f64 = 1e-9;
+
+/// Returns the outline of the cells in `leaves` at resolution `res`
+/// or coarser, where `covers` reports whether a cell is entirely
+/// within the set.
+pub(crate) fn dissolve<L, F>(leaves: L, res: u8, covers: F) -> MultiPolygon<f64>
+where
+    L: IntoIterator<Item = Cell>,
+    F: Fn(Cell) -> bool,
+{
+    let mut outline = Outline::default();
+    let mut stack = Vec::new();
+    for leaf in leaves {
+        stack.push(leaf);
+        while let Some(cell) = stack.pop() {
+            let edges = edges(cell);
+            if cell.res() == res {
+                for edge in edges {
+                    if !covers(edge.neighbor) {
+                        outline.insert(edge);
+                    }
+                }
+            } else if !edges.iter().all(|edge| covers(edge.neighbor)) {
+                // A cell's descendants only ever border its neighbors'
+                // descendants, so a cell surrounded by covered cells
+                // can't contribute to the outline.
+                stack.extend(cell.children(cell.res() + 1));
+            }
+        }
+    }
+    outline.into_multipolygon()
+}
+
+/// One edge of a cell, from one corner to the next.
+struct Edge {
+    /// The edge's first corner, followed by any points where it
+    /// crosses an icosahedron edge, as (lat, lng) degrees.
+    points: Vec<(f64, f64)>,
+    /// The edge's second corner.
+    end: (f64, f64),
+    /// The cell on the other side of this edge.
+    neighbor: Cell,
+}
+
+/// Returns `cell`'s edges, counterclockwise.
+fn edges(cell: Cell) -> Vec<Edge> {
+    let boundary = index_to_boundary(Index::from_raw(cell.into_raw()));
+    let center = to_xyz(index_to_latlng(Index::from_raw(cell.into_raw())));
+    let corners: Vec<usize> = (0..boundary.len())
+        .filter(|&i| boundary[i].topological)
+        .collect();
+    (0..corners.len())
+        .map(|i| {
+            let (first, last) = (corners[i], corners[(i + 1) % corners.len()]);
+            let points = (first..)
+                .map(|j| boundary[j % boundary.len()].latlng)
+                .take((last + boundary.len() - first - 1) % boundary.len() + 1)
+                .collect::<Vec<_>>();
+            let end = boundary[last].latlng;
+
+            // Step from the center halfway past the edge's midpoint,
+            // which lands well inside the neighboring cell.
+            let (a, b) = (to_xyz(points[0]), to_xyz(end));
+            let outside = [0, 1, 2].map(|axis| {
+                let mid = (a[axis] + b[axis]) / 2.0;
+                center[axis] + 1.5 * (mid - center[axis])
+            });
+            let (lat, lng) = to_latlng(outside);
+            let neighbor = Cell::from_latlng(lat, lng, cell.res())
+                .expect("point outside a cell edge is valid");
+            Edge {
+                points,
+                end,
+                neighbor,
+            }
+        })
+        .collect()
+}
+
+/// Returns the point on the unit sphere at `lat`, `lng` degrees.
+fn to_xyz((lat, lng): (f64, f64)) -> [f64; 3] {
+    let (lat, lng) = (lat.to_radians(), lng.to_radians());
+    [lat.cos() * lng.cos(), lat.cos() * lng.sin(), lat.sin()]
+}
+
+/// Returns the (lat, lng) degrees of the point in direction `xyz`
+/// from the center of the earth.
+fn to_latlng([x, y, z]: [f64; 3]) -> (f64, f64) {
+    (z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+}
+
+/// The outward facing edges of a set of cells, keyed by their corners.
+#[derive(Default)]
+struct Outline {
+    /// Corner ids by their position, rounded to `CORNER_GRID`.
+    ids: HashMap<[i64; 3], usize>,
+    /// Corner positions by id.
+    corners: Vec<[f64; 3]>,
+    /// Edges' points and their last corner's id, by their first
+    /// corner's id.
+    ///
+    /// Every corner is shared by exactly 3 cells, so an outline never
+    /// passes through a corner more than once.
+    edges: BTreeMap<usize, (Vec<(f64, f64)>, usize)>,
+}
+
+impl Outline {
+    fn insert(&mut self, edge: Edge) {
+        let first = self.corner(edge.points[0]);
+        let last = self.corner(edge.end);
+        let prev = self.edges.insert(first, (edge.points, last));
+        assert!(prev.is_none(), "outline passes through a corner twice");
+    }
+
+    /// Returns the id of the corner at `latlng`.
+    fn corner(&mut self, latlng: (f64, f64)) -> usize {
+        let xyz = to_xyz(latlng);
+        let key = xyz.map(|c| (c / CORNER_GRID).round() as i64);
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+        // The same corner computed from a different cell may round
+        // into an adjacent bucket.
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let adjacent = [key[0] + di, key[1] + dj, key[2] + dk];
+                    if let Some(&id) = self.ids.get(&adjacent) {
+                        let corner = self.corners[id];
+                        if (0..3).all(|axis| (corner[axis] - xyz[axis]).abs() < CORNER_GRID) {
+                            return id;
+                        }
+                    }
+                }
+            }
+        }
+        let id = self.corners.len();
+        self.corners.push(xyz);
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// Chains edges into rings and nests holes in the smallest ring
+    /// around them.
+    fn into_multipolygon(mut self) -> MultiPolygon<f64> {
+        let mut rings = Vec::new();
+        let mut polar = Vec::new();
+        while let Some(&start) = self.edges.keys().next() {
+            let mut ring = Vec::new();
+            let mut corner = start;
+            loop {
+                let (points, next) = self
+                    .edges
+                    .remove(&corner)
+                    .expect("outline edges form closed rings");
+                for (lat, lng) in points {
+                    // Keep rings crossing the antimeridian continuous.
+                    let x = ring
+                        .last()
+                        .map_or(lng, |prev: &Coord<f64>| unwrap_lng(prev.x, lng));
+                    ring.push(Coord { x, y: lat });
+                }
+                corner = next;
+                if corner == start {
+                    break;
+                }
+            }
+            if turn(&ring).abs() < 180.0 {
+                rings.push(Ring::new(ring));
+            } else {
+                polar.push(ring);
+            }
+        }
+        rings.extend(close_polar(polar));
+
+        // Cell boundaries are counterclockwise, so the outside of a
+        // region is too, and its holes are clockwise.
+        let (exteriors, holes): (Vec<_>, Vec<_>) =
+            rings.into_iter().partition(|ring| ring.area > 0.0);
+        let mut exteriors: Vec<_> = exteriors
+            .into_iter()
+            .map(|ring| (ring, Vec::new()))
+            .collect();
+        let mut uncontained = Vec::new();
+        for hole in holes {
+            let point = hole.coords[0];
+            // Either ring may have been unwrapped past the antimeridian.
+            let exterior = exteriors
+                .iter_mut()
+                .filter(|(exterior, _)| {
+                    [0.0, 360.0, -360.0].iter().any(|shift| {
+                        exterior.contains(Coord {
+                            x: point.x + shift,
+                            y: point.y,
+                        })
+                    })
+                })
+                .min_by(|(a, _), (b, _)| a.area.total_cmp(&b.area));
+            match exterior {
+                Some((_, interiors)) => interiors.push(LineString::from(hole.coords)),
+                None => uncontained.push(LineString::from(hole.coords)),
+            }
+        }
+
+        let mut polygons: Vec<_> = exteriors
+            .into_iter()
+            .map(|(exterior, interiors)| Polygon::new(LineString::from(exterior.coords), interiors))
+            .collect();
+        // A hole outside every ring is in a set covering the rest of
+        // the world.
+        if !uncontained.is_empty() {
+            let world = LineString::from(vec![
+                (-180.0, -90.0),
+                (180.0, -90.0),
+                (180.0, 90.0),
+                (-180.0, 90.0),
+            ]);
+            polygons.push(Polygon::new(world, uncontained));
+        }
+        MultiPolygon(polygons)
+    }
+}
+
+/// Returns how far east `ring` turns around the earth's axis on its
+/// way back to its start: 0 unless it goes around a pole, and ±360°
+/// if it does.
+fn turn(ring: &[Coord<f64>]) -> f64 {
+    let (first, last) = (ring[0], ring[ring.len() - 1]);
+    unwrap_lng(last.x, first.x) - first.x
+}
+
+/// Closes rings around a pole, which end a whole turn from where they
+/// start, by following the meridians from each end to a pole and
+/// the pole between them.
+///
+/// Rings heading west have the set to their south, so closing them
+/// along the south pole makes an exterior, and closing rings heading
+/// east along it makes a hole in the exterior of the ring north of
+/// them. Only if the northernmost ring heads east, where the set
+/// covers the north pole, is that one closed along the north pole,
+/// into an exterior of its own.
+fn close_polar(rings: Vec<Vec<Coord<f64>>>) -> Vec<Ring> {
+    // Rings around a pole don't cross, so the farther one is from the
+    // north pole, the more area lies between them.
+    let mut rings: Vec<(f64, Vec<Coord<f64>>)> = rings
+        .into_iter()
+        .map(|ring| (Ring::new(close_along(ring.clone(), 90.0)).area.abs(), ring))
+        .collect();
+    rings.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    rings
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, ring))| {
+            let pole = if i == 0 && turn(&ring) > 0.0 {
+                90.0
+            } else {
+                -90.0
+            };
+            Ring::new(close_along(ring, pole))
+        })
+        .collect()
+}
+
+/// Closes `ring`, which goes around a pole, along the pole at `pole`
+/// degrees latitude.
+fn close_along(mut ring: Vec<Coord<f64>>, pole: f64) -> Vec<Coord<f64>> {
+    let first = ring[0];
+    let x = first.x + turn(&ring);
+    ring.extend([
+        Coord { x, y: first.y },
+        Coord { x, y: pole },
+        Coord {
+            x: first.x,
+            y: pole,
+        },
+    ]);
+    ring
+}
+
+/// A closed ring of (lng, lat) degrees.
+struct Ring {
+    coords: Vec<Coord<f64>>,
+    /// Signed area, positive if counterclockwise.
+    area: f64,
+    min: Coord<f64>,
+    max: Coord<f64>,
+}
+
+impl Ring {
+    fn new(coords: Vec<Coord<f64>>) -> Self {
+        let mut min = coords[0];
+        let mut max = coords[0];
+        let mut area = 0.0;
+        for (i, a) in coords.iter().enumerate() {
+            let b = coords[(i + 1) % coords.len()];
+            area += a.x * b.y - b.x * a.y;
+            min = Coord {
+                x: min.x.min(a.x),
+                y: min.y.min(a.y),
+            };
+            max = Coord {
+                x: max.x.max(a.x),
+                y: max.y.max(a.y),
+            };
+        }
+        Self {
+            coords,
+            area: area / 2.0,
+            min,
+            max,
+        }
+    }
+
+    /// Returns `true` if `point` is inside this ring.
+    fn contains(&self, point: Coord<f64>) -> bool {
+        if point.x < self.min.x
+            || point.x > self.max.x
+            || point.y < self.min.y
+            || point.y > self.max.y
+        {
+            return false;
+        }
+        ring_contains(&self.coords, point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "outline passes through a corner twice")]
+    fn test_outline_rejects_reused_corner() {
+        let cell = Cell::from_raw(0x863969a47ffffff).unwrap();
+        let mut outline = Outline::default();
+        for edge in edges(cell).into_iter().chain(edges(cell)) {
+            outline.insert(edge);
+        }
+    }
+}
@@ -0,0 +1,522 @@
+//! Moving between neighboring cells of the same resolution.
+//!
+//! A step to a neighbor is an `ijk` unit vector added to the finest
+//! digit, carrying into coarser digits like addition carries into
+//! higher places, and possibly into a neighboring base cell. Base cells
+//! have their own coordinate systems, rotated relative to each other,
+//! so the direction of later steps has to rotate along with them.
+
+use super::{
+    coord::CoordIJK,
+    is_class_iii, is_cw_offset, leading_digit, rotate60_ccw, rotate60_cw, rotate_pent60_ccw,
+    tables::{BASE_CELL_DATA, BASE_CELL_NEIGHBORS, BASE_CELL_NEIGHBOR_ROTATIONS, NO_NEIGHBOR},
+    IK_AXIS_DIGIT, SQRT7,
+};
+use crate::cell::{is_pentagon_base, Index, K_AXIS_DIGIT};
+use crate::Cell;
+use std::collections::{HashSet, VecDeque};
+
+/// `ijk` direction of the digit at a pentagon's `jk` edge.
+const JK_AXIS_DIGIT: u8 = 3;
+
+/// Digit after stepping in a direction, by current digit and
+/// direction, at Class II resolutions.
+const NEW_DIGIT_II: [[u8; 7]; 7] = [
+    [0, 1, 2, 3, 4, 5, 6],
+    [1, 4, 3, 6, 5, 2, 0],
+    [2, 3, 1, 4, 6, 0, 5],
+    [3, 6, 4, 5, 0, 1, 2],
+    [4, 5, 6, 0, 2, 3, 1],
+    [5, 2, 0, 1, 3, 6, 4],
+    [6, 0, 5, 2, 1, 4, 3],
+];
+
+/// Direction carried into the next coarser digit after stepping in a
+/// direction, by current digit and direction, at Class II resolutions.
+const NEW_ADJUSTMENT_II: [[u8; 7]; 7] = [
+    [0, 0, 0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0, 5, 0],
+    [0, 0, 2, 3, 0, 0, 2],
+    [0, 1, 3, 3, 0, 0, 0],
+    [0, 0, 0, 0, 4, 4, 6],
+    [0, 5, 0, 0, 4, 5, 0],
+    [0, 0, 2, 0, 6, 0, 6],
+];
+
+/// Digit after stepping in a direction, by current digit and
+/// direction, at Class III resolutions.
+const NEW_DIGIT_III: [[u8; 7]; 7] = [
+    [0, 1, 2, 3, 4, 5, 6],
+    [1, 2, 3, 4, 5, 6, 0],
+    [2, 3, 4, 5, 6, 0, 1],
+    [3, 4, 5, 6, 0, 1, 2],
+    [4, 5, 6, 0, 1, 2, 3],
+    [5, 6, 0, 1, 2, 3, 4],
+    [6, 0, 1, 2, 3, 4, 5],
+];
+
+/// Direction carried into the next coarser digit after stepping in a
+/// direction, by current digit and direction, at Class III
+/// resolutions.
+const NEW_ADJUSTMENT_III: [[u8; 7]; 7] = [
+    [0, 0, 0, 0, 0, 0, 0],
+    [0, 1, 0, 3, 0, 1, 0],
+    [0, 0, 2, 2, 0, 0, 6],
+    [0, 3, 2, 3, 0, 0, 0],
+    [0, 0, 0, 0, 4, 5, 4],
+    [0, 1, 0, 0, 5, 5, 0],
+    [0, 0, 6, 0, 4, 0, 6],
+];
+
+/// Number of 60 degree cw rotations which unfold the coordinates of
+/// a pentagon's subsequence, by the digits on either side of the
+/// unfolding.
+const PENTAGON_ROTATIONS: [[u8; 7]; 7] = [
+    [0, 0xff, 0, 0, 0, 0, 0],
+    [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+    [0, 0xff, 0, 0, 0, 1, 0],
+    [0, 0xff, 0, 0, 1, 1, 0],
+    [0, 0xff, 0, 5, 0, 0, 0],
+    [0, 0xff, 5, 5, 0, 0, 0],
+    [0, 0xff, 0, 0, 0, 0, 0],
+];
+
+/// Bits `a * 7 + b` set for pairs of leading digits which a pentagon
+/// can't be unfolded between.
+const FAILED_DIRECTIONS: u64 = 0b0101000_1000100_0001100_1010000_0110000_0000000_0000000;
+
+/// Returns the same resolution neighbor of `idx` in `ijk` direction
+/// `digit` after rotating that direction `rotations` times 60°
+/// counterclockwise, and the rotations to apply to further steps in
+/// the same direction.
+///
+/// Returns `None` for a step into a pentagon's deleted k-axis
+/// subsequence.
+pub(crate) fn neighbor_rotations(idx: Index, digit: u8, mut rotations: u8) -> Option<(Index, u8)> {
+    debug_assert!((1..7).contains(&digit));
+    let mut current = idx;
+    let mut dir = (0..rotations).fold(digit, |dir, _| rotate_digit60_ccw(dir));
+
+    // Add `dir` to each digit from the finest up, until there's
+    // nothing left to carry.
+    let mut res = idx.res();
+    while res > 0 {
+        let old = current.digit(res).expect("res is in [1, 15]") as usize;
+        let (new_digit, adjustment) = if is_class_iii(res) {
+            (NEW_DIGIT_II, NEW_ADJUSTMENT_II)
+        } else {
+            (NEW_DIGIT_III, NEW_ADJUSTMENT_III)
+        };
+        current = current.set_digit(res, new_digit[old][dir as usize]);
+        dir = adjustment[old][dir as usize];
+        if dir == 0 {
+            break;
+        }
+        res -= 1;
+    }
+
+    let old_base = idx.base();
+    let new_rotations = if res == 0 {
+        let neighbor = BASE_CELL_NEIGHBORS[old_base as usize][dir as usize];
+        if neighbor == NO_NEIGHBOR {
+            // This edge of the pentagon actually borders the neighbor
+            // across its ik edge, past the deleted k subsequence.
+            current = rotate60_ccw(
+                current.set_base(BASE_CELL_NEIGHBORS[old_base as usize][IK_AXIS_DIGIT as usize]),
+            );
+            rotations += 1;
+            BASE_CELL_NEIGHBOR_ROTATIONS[old_base as usize][IK_AXIS_DIGIT as usize]
+        } else {
+            current = current.set_base(neighbor);
+            BASE_CELL_NEIGHBOR_ROTATIONS[old_base as usize][dir as usize]
+        }
+    } else {
+        0
+    };
+
+    let new_base = current.base();
+    if is_pentagon_base(new_base) {
+        let mut already_adjusted_k_subsequence = false;
+
+        // Force rotation out of the deleted k subsequence.
+        if leading_digit(current) == Some(K_AXIS_DIGIT) {
+            if old_base == new_base {
+                // Stepped into the deleted subsequence from within the
+                // same pentagon.
+                match leading_digit(idx)? {
+                    JK_AXIS_DIGIT => {
+                        current = rotate60_ccw(current);
+                        rotations += 1;
+                    }
+                    IK_AXIS_DIGIT => {
+                        current = rotate60_cw(current);
+                        rotations += 5;
+                    }
+                    _ => (),
+                }
+            } else {
+                // Stepped into the deleted subsequence from another
+                // base cell, which is clockwise offset or not.
+                let old_face = BASE_CELL_DATA[old_base as usize].home_face;
+                if is_cw_offset(new_base, old_face) {
+                    current = rotate60_cw(current);
+                }
+                already_adjusted_k_subsequence = true;
+            }
+        }
+
+        for _ in 0..new_rotations {
+            current = rotate_pent60_ccw(current);
+        }
+
+        // Account for the differing orientation of the base cells.
+        if old_base != new_base {
+            let leading = leading_digit(current);
+            if new_base == 4 || new_base == 117 {
+                // Polar pentagons have all of their neighbors oriented
+                // towards them.
+                if old_base != 118 && old_base != 8 && leading != Some(JK_AXIS_DIGIT) {
+                    rotations += 1;
+                }
+            } else if !already_adjusted_k_subsequence && leading == Some(IK_AXIS_DIGIT) {
+                // The deleted k subsequence distorts the pentagon's ik
+                // neighbor.
+                rotations += 1;
+            }
+        }
+    } else {
+        for _ in 0..new_rotations {
+            current = rotate60_ccw(current);
+        }
+    }
+
+    Some((current, (rotations + new_rotations) % 6))
+}
+
+/// Returns `digit` rotated 60° counterclockwise.
+fn rotate_digit60_ccw(digit: u8) -> u8 {
+    [0, 5, 3, 1, 6, 4, 2][digit as usize]
+}
+
+/// Returns `digit` rotated 60° clockwise.
+fn rotate_digit60_cw(digit: u8) -> u8 {
+    [0, 3, 6, 2, 5, 1, 4][digit as usize]
+}
+
+/// Returns `cell`'s neighbors, in `ijk` direction order.
+pub(crate) fn neighbors(cell: Cell) -> Vec<Cell> {
+    let mut neighbors = Vec::with_capacity(6);
+    for digit in 1..7 {
+        if let Some((idx, _)) = neighbor_rotations(Index::from_raw(cell.into_raw()), digit, 0) {
+            let neighbor = Cell(idx.into_raw());
+            if !neighbors.contains(&neighbor) {
+                neighbors.push(neighbor);
+            }
+        }
+    }
+    neighbors
+}
+
+/// `ijk` direction stepped `k` times from a ring's origin to reach
+/// its first cell.
+const RING_START_DIGIT: u8 = 4;
+
+/// `ijk` directions of the six sides walked around a ring, starting
+/// from the cell reached with [`RING_START_DIGIT`].
+const RING_SIDE_DIGITS: [u8; 6] = [2, 3, 1, 5, 4, 6];
+
+/// Returns the cells exactly `k` steps from `origin`.
+///
+/// The ring is walked directly, stepping `k` times outward and then
+/// `k` times along each of its six sides. That walk is only valid
+/// where the grid has consistent `ijk` coordinates, so near a
+/// pentagon this falls back to filtering a [`Disk`].
+pub(crate) fn ring(origin: Cell, k: u32) -> Vec<Cell> {
+    walk_ring(origin, k).unwrap_or_else(|| {
+        Disk::new(origin, k)
+            .filter(|&(_, distance)| distance == k)
+            .map(|(cell, _)| cell)
+            .collect()
+    })
+}
+
+/// Returns the cells exactly `k` steps from `origin` by walking
+/// around them, or `None` if the walk meets a pentagon.
+fn walk_ring(origin: Cell, k: u32) -> Option<Vec<Cell>> {
+    if k == 0 {
+        return Some(vec![origin]);
+    }
+    let is_pentagon = |idx: Index| Cell(idx.into_raw()).is_pentagon();
+    let mut current = Index::from_raw(origin.into_raw());
+    if is_pentagon(current) {
+        return None;
+    }
+    let mut rotations = 0;
+    for _ in 0..k {
+        let (next, next_rotations) = neighbor_rotations(current, RING_START_DIGIT, rotations)?;
+        if is_pentagon(next) {
+            return None;
+        }
+        current = next;
+        rotations = next_rotations;
+    }
+
+    let first = current;
+    let mut cells = Vec::with_capacity(6 * k as usize);
+    for &digit in &RING_SIDE_DIGITS {
+        for _ in 0..k {
+            cells.push(Cell(current.into_raw()));
+            let (next, next_rotations) = neighbor_rotations(current, digit, rotations)?;
+            if is_pentagon(next) {
+                return None;
+            }
+            current = next;
+            rotations = next_rotations;
+        }
+    }
+    // Not arriving back at the start means pentagon distortion
+    // somewhere inside the ring.
+    if current == first {
+        Some(cells)
+    } else {
+        None
+    }
+}
+
+/// An iterator over the cells within `k` steps of an origin, with
+/// their distance from it, in order of increasing distance.
+///
+/// This is a breadth-first search, so it is correct around pentagons,
+/// where the grid has no consistent `ijk` coordinates.
+pub(crate) struct Disk {
+    k: u32,
+    seen: HashSet<Cell>,
+    queue: VecDeque<(Cell, u32)>,
+}
+
+impl Disk {
+    pub(crate) fn new(origin: Cell, k: u32) -> Self {
+        Self {
+            k,
+            seen: std::iter::once(origin).collect(),
+            queue: std::iter::once((origin, 0)).collect(),
+        }
+    }
+}
+
+impl Iterator for Disk {
+    type Item = (Cell, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (cell, distance) = self.queue.pop_front()?;
+        if distance < self.k {
+            for neighbor in neighbors(cell) {
+                if self.seen.insert(neighbor) {
+                    self.queue.push_back((neighbor, distance + 1));
+                }
+            }
+        }
+        Some((cell, distance))
+    }
+}
+
+/// Returns the number of steps between `origin` and `other`, or
+/// `None` if they aren't on the same or neighboring base cells, or
+/// are on opposite sides of a pentagon's distortion.
+pub(crate) fn grid_distance(origin: Cell, other: Cell) -> Option<u32> {
+    let origin = Index::from_raw(origin.into_raw());
+    let other = Index::from_raw(other.into_raw());
+    if origin.res() != other.res() {
+        return None;
+    }
+    let a = to_local_ijk(origin, origin)?;
+    let b = to_local_ijk(origin, other)?;
+    Some(a.distance(b) as u32)
+}
+
+/// Returns the number of steps from `origin` to the nearest of
+/// `cell`'s descendants at `origin`'s resolution, and whether that's
+/// exact rather than a lower bound.
+///
+/// If `cell` is at least as fine as `origin`, this is the exact
+/// [`grid_distance`] to its ancestor at `origin`'s resolution.
+/// Otherwise it's a lower bound found at `cell`'s resolution: the
+/// `ijk` grid of each resolution is the one before scaled by
+/// `sqrt(7)`, and each digit moves a descendant's center at most one
+/// step of its own resolution away from its ancestor's, so scaling the
+/// distance between `cell` and `origin`'s ancestor at that resolution
+/// bounds how far apart their descendants can be. Pentagons break up
+/// the grid, so the bound is 0 near them.
+///
+/// Returns `None` if [`grid_distance`] can't measure the distance to
+/// any of those descendants, either because `cell` is too far away
+/// from `origin` or, for an exact distance, across a pentagon.
+pub(crate) fn distance_bound(origin: Cell, cell: Cell) -> Option<(u32, bool)> {
+    let res = origin.res();
+    if cell.res() >= res {
+        let ancestor = cell
+            .to_parent(res)
+            .expect("cell is at least as fine as origin");
+        return grid_distance(origin, ancestor).map(|distance| (distance, true));
+    }
+    let (origin_base, base) = (origin.base(), cell.base());
+    if origin_base != base {
+        direction(origin_base, base)?;
+    }
+    if is_pentagon_base(origin_base) || is_pentagon_base(base) {
+        return Some((0, false));
+    }
+
+    let coarse_res = cell.res();
+    let coarse_origin = Index::from_raw(
+        origin
+            .to_parent(coarse_res)
+            .expect("cell is coarser than origin")
+            .into_raw(),
+    );
+    let (a, b) = match (
+        to_local_ijk(coarse_origin, coarse_origin),
+        to_local_ijk(coarse_origin, Index::from_raw(cell.into_raw())),
+    ) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Some((0, false)),
+    };
+    // Digits can move both cells' descendants at most
+    // `sum(sqrt(7)^i for i in 0..levels)` steps of `origin`'s
+    // resolution, which is less than `scale / (sqrt(7) - 1)`.
+    let scale = SQRT7.powi(i32::from(res - coarse_res));
+    let distance = (b - a).to_hex2d().magnitude();
+    let bound = (distance - 2.0 / (SQRT7 - 1.0)) * scale;
+    Some((bound.max(0.0).floor() as u32, false))
+}
+
+/// Returns the `ijk` coordinates of `idx` in the coordinate system of
+/// `origin`'s base cell, unfolded across a base cell edge if needed.
+fn to_local_ijk(origin: Index, mut idx: Index) -> Option<CoordIJK> {
+    let res = idx.res();
+    let origin_base = origin.base();
+    let base = idx.base();
+    let origin_on_pent = is_pentagon_base(origin_base);
+    let idx_on_pent = is_pentagon_base(base);
+
+    // Direction from the origin's base cell to `idx`'s, and back.
+    let (dir, mut rev_dir) = if origin_base == base {
+        (0, 0)
+    } else {
+        (
+            direction(origin_base, base)?,
+            direction(base, origin_base).expect("base cell neighbors are symmetric"),
+        )
+    };
+
+    if dir != 0 {
+        // Rotate `idx` into the orientation of the origin's base cell.
+        let rotations = BASE_CELL_NEIGHBOR_ROTATIONS[origin_base as usize][dir as usize];
+        if idx_on_pent {
+            for _ in 0..rotations {
+                idx = rotate_pent60_cw(idx);
+                // Skip over the deleted k axis.
+                rev_dir = rotate_digit60_cw(rev_dir);
+                if rev_dir == K_AXIS_DIGIT {
+                    rev_dir = rotate_digit60_cw(rev_dir);
+                }
+            }
+        } else {
+            for _ in 0..rotations {
+                idx = rotate60_cw(idx);
+                rev_dir = rotate_digit60_cw(rev_dir);
+            }
+        }
+    }
+
+    // Coordinates within `idx`'s base cell.
+    let mut ijk = CoordIJK::default();
+    for r in 1..=res {
+        ijk = if is_class_iii(r) {
+            ijk.down_ap7()
+        } else {
+            ijk.down_ap7r()
+        };
+        ijk = ijk.neighbor(idx.digit(r).expect("res is in [1, 15]"));
+    }
+
+    if dir != 0 {
+        debug_assert!(!(origin_on_pent && idx_on_pent));
+        let (pentagon_rotations, dir_rotations) = if origin_on_pent {
+            let leading = leading_digit(origin).unwrap_or(0);
+            if !can_unfold(leading, dir) {
+                return None;
+            }
+            let rotations = PENTAGON_ROTATIONS[leading as usize][dir as usize];
+            (rotations, rotations)
+        } else if idx_on_pent {
+            let leading = leading_digit(idx).unwrap_or(0);
+            if !can_unfold(leading, rev_dir) {
+                return None;
+            }
+            (PENTAGON_ROTATIONS[rev_dir as usize][leading as usize], 0)
+        } else {
+            (0, 0)
+        };
+        for _ in 0..pentagon_rotations {
+            ijk = ijk.rotate60_cw();
+        }
+
+        // Offset by the neighboring base cell's position, scaled down
+        // to `res`.
+        let mut offset = CoordIJK::default().neighbor(dir);
+        for r in (1..=res).rev() {
+            offset = if is_class_iii(r) {
+                offset.down_ap7()
+            } else {
+                offset.down_ap7r()
+            };
+        }
+        for _ in 0..dir_rotations {
+            offset = offset.rotate60_cw();
+        }
+        ijk = (ijk + offset).normalize();
+    } else if origin_on_pent && idx_on_pent {
+        let origin_leading = leading_digit(origin).unwrap_or(0);
+        let idx_leading = leading_digit(idx).unwrap_or(0);
+        if !can_unfold(origin_leading, idx_leading) {
+            return None;
+        }
+        for _ in 0..PENTAGON_ROTATIONS[origin_leading as usize][idx_leading as usize] {
+            ijk = ijk.rotate60_cw();
+        }
+    }
+    Some(ijk)
+}
+
+/// Returns `false` if unfolding a pentagon between leading digits
+/// `a` and `b` isn't supported.
+fn can_unfold(a: u8, b: u8) -> bool {
+    FAILED_DIRECTIONS & (1 << (a * 7 + b)) == 0
+}
+
+/// Returns the direction from base cell `from` to neighboring base
+/// cell `to`.
+fn direction(from: u8, to: u8) -> Option<u8> {
+    BASE_CELL_NEIGHBORS[from as usize]
+        .iter()
+        .position(|&neighbor| neighbor == to)
+        .map(|dir| dir as u8)
+}
+
+/// Rotates every digit of pentagonal `idx` 60° clockwise, skipping
+/// over the deleted k-axis subsequence.
+fn rotate_pent60_cw(mut idx: Index) -> Index {
+    let mut found_first_non_zero = false;
+    for res in 1..=idx.res() {
+        let digit = idx.digit(res).expect("res is in [1, 15]");
+        idx = idx.set_digit(res, rotate_digit60_cw(digit));
+        if !found_first_non_zero && idx.digit(res) != Some(0) {
+            found_first_non_zero = true;
+            if leading_digit(idx) == Some(K_AXIS_DIGIT) {
+                idx = rotate60_cw(idx);
+            }
+        }
+    }
+    idx
+}
@@ -0,0 +1,888 @@
+//! Pure-Rust conversion between geographic coordinates and H3 cells.
+//!
+//! This follows the algorithms of the [H3 reference
+//! implementation](https://github.com/uber/h3): a point is projected
+//! onto the closest face of an icosahedron with a gnomonic
+//! projection, snapped to that face's hex grid, and the resulting
+//! face-relative coordinates are rotated into the coordinate system of
+//! the containing base cell.
+
+mod coord;
+mod dissolve;
+mod grid;
+mod nearest;
+mod polyfill;
+mod tables;
+
+pub use self::polyfill::ContainmentMode;
+use self::{
+    coord::{CoordIJK, FaceIJK, Vec2d, SQRT3_2},
+    tables::{
+        BASE_CELL_DATA, FACE_AXES_AZ_RADS_CII, FACE_CENTER_GEO, FACE_CENTER_POINT,
+        FACE_IJK_BASE_CELLS, FACE_NEIGHBORS, IJ, JK, KI,
+    },
+};
+pub(crate) use self::{
+    dissolve::dissolve,
+    grid::{grid_distance, neighbors, ring, Disk},
+    nearest::k_nearest,
+    polyfill::polyfill,
+};
+use crate::{
+    cell::{is_pentagon_base, Index, K_AXIS_DIGIT},
+    Cell,
+};
+use geo_types::Coord;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Threshold below which angles and distances are treated as zero.
+const EPSILON: f64 = 0.000_000_000_000_000_1;
+
+/// Scaling factor from unit gnomonic distance to resolution 0 hex
+/// distance.
+const RES0_U_GNOMONIC: f64 = 0.381_966_011_250_105;
+
+/// Rotation in radians between a Class II and Class III resolution.
+const AP7_ROT_RADS: f64 = 0.333_473_172_251_832_1;
+
+/// `sqrt(7)`, the scaling factor between successive resolutions.
+const SQRT7: f64 = 2.645_751_311_064_590_7;
+
+/// `ijk` direction of the digit which replaces a pentagon's deleted
+/// k-axis when rotating out of it.
+const IK_AXIS_DIGIT: u8 = 5;
+
+/// `ijk` direction of a pentagon's leading digit which may need
+/// adjusting for its missing sequence.
+const I_AXIS_DIGIT: u8 = 4;
+
+/// Returns `true` if `res` is rotated relative to the icosahedron's
+/// Class II orientation.
+fn is_class_iii(res: u8) -> bool {
+    res % 2 == 1
+}
+
+/// Returns the number of resolution `res` unit lengths from a face's
+/// center to its edge. `res` must be Class II.
+fn max_dim_by_cii_res(res: u8) -> i32 {
+    2 * unit_scale_by_cii_res(res)
+}
+
+/// Returns the length of a resolution 0 unit vector in resolution
+/// `res` units. `res` must be Class II.
+fn unit_scale_by_cii_res(res: u8) -> i32 {
+    debug_assert!(!is_class_iii(res));
+    7_i32.pow(u32::from(res / 2))
+}
+
+/// Normalizes `angle` radians into `[0, 2π)`.
+fn pos_angle(angle: f64) -> f64 {
+    if angle < 0.0 {
+        angle + 2.0 * PI
+    } else if angle >= 2.0 * PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Normalizes `lng` radians into `[-π, π]`.
+fn constrain_lng(mut lng: f64) -> f64 {
+    while lng > PI {
+        lng -= 2.0 * PI;
+    }
+    while lng < -PI {
+        lng += 2.0 * PI;
+    }
+    lng
+}
+
+/// Returns the azimuth in radians from `from` to `to`, both (lat, lng)
+/// radians.
+fn azimuth((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    (lat2.cos() * (lng2 - lng1).sin())
+        .atan2(lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * (lng2 - lng1).cos())
+}
+
+/// Returns the great circle distance in radians between two (lat, lng)
+/// radian points.
+fn great_circle_distance((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    let sin_lat = ((lat2 - lat1) / 2.0).sin();
+    let sin_lng = ((lng2 - lng1) / 2.0).sin();
+    let a = sin_lat * sin_lat + lat1.cos() * lat2.cos() * sin_lng * sin_lng;
+    2.0 * a.sqrt().min(1.0).asin()
+}
+
+/// Returns the point `distance` radians away from `origin` along
+/// azimuth `az`, all in radians.
+fn az_distance((lat1, lng1): (f64, f64), az: f64, distance: f64) -> (f64, f64) {
+    if distance < EPSILON {
+        return (lat1, lng1);
+    }
+    let az = pos_angle(az);
+
+    let pole = |lat: f64| {
+        if (lat - FRAC_PI_2).abs() < EPSILON {
+            Some((FRAC_PI_2, 0.0))
+        } else if (lat + FRAC_PI_2).abs() < EPSILON {
+            Some((-FRAC_PI_2, 0.0))
+        } else {
+            None
+        }
+    };
+
+    // Due north or south.
+    if az < EPSILON || (az - PI).abs() < EPSILON {
+        let lat = if az < EPSILON {
+            lat1 + distance
+        } else {
+            lat1 - distance
+        };
+        return pole(lat).unwrap_or((lat, constrain_lng(lng1)));
+    }
+
+    let sin_lat =
+        (lat1.sin() * distance.cos() + lat1.cos() * distance.sin() * az.cos()).clamp(-1.0, 1.0);
+    let lat = sin_lat.asin();
+    if let Some(pole) = pole(lat) {
+        return pole;
+    }
+    let inv_cos_lat = 1.0 / lat.cos();
+    let sin_lng = (az.sin() * distance.sin() * inv_cos_lat).clamp(-1.0, 1.0);
+    let cos_lng =
+        ((distance.cos() - lat1.sin() * lat.sin()) / lat1.cos() * inv_cos_lat).clamp(-1.0, 1.0);
+    (lat, constrain_lng(lng1 + sin_lng.atan2(cos_lng)))
+}
+
+/// A point projected onto its closest icosahedron face.
+///
+/// The projection is independent of resolution, so converting the
+/// same point at several resolutions only pays for the trigonometry
+/// once.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Gnomonic {
+    face: u8,
+    /// Distance from the face center in resolution 0 units.
+    r: f64,
+    /// Class II angle from the face's i-axis.
+    theta: f64,
+}
+
+impl Gnomonic {
+    /// Projects `lat`, `lng` degrees, returning `None` if either is
+    /// not finite.
+    pub(crate) fn new(lat: f64, lng: f64) -> Option<Self> {
+        if !lat.is_finite() || !lng.is_finite() {
+            return None;
+        }
+        let point = (lat.to_radians(), lng.to_radians());
+
+        let (x, y, z) = {
+            let r = point.0.cos();
+            (point.1.cos() * r, point.1.sin() * r, point.0.sin())
+        };
+        let (face, sq_dist) = FACE_CENTER_POINT
+            .iter()
+            .map(|c| (c[0] - x).powi(2) + (c[1] - y).powi(2) + (c[2] - z).powi(2))
+            .enumerate()
+            .fold((0, f64::INFINITY), |closest, (face, sq_dist)| {
+                if sq_dist < closest.1 {
+                    (face, sq_dist)
+                } else {
+                    closest
+                }
+            });
+
+        // Great circle angle between the point and the face center.
+        let r = (1.0 - sq_dist / 2.0).acos();
+        if r < EPSILON {
+            return Some(Self {
+                face: face as u8,
+                r: 0.0,
+                theta: 0.0,
+            });
+        }
+        let theta = pos_angle(
+            FACE_AXES_AZ_RADS_CII[face][0] - pos_angle(azimuth(FACE_CENTER_GEO[face], point)),
+        );
+        Some(Self {
+            face: face as u8,
+            r: r.tan() / RES0_U_GNOMONIC,
+            theta,
+        })
+    }
+
+    /// Returns the index of the resolution `res` cell containing this
+    /// point.
+    pub(crate) fn to_index(self, res: u8) -> Index {
+        let mut theta = self.theta;
+        if is_class_iii(res) {
+            theta = pos_angle(theta - AP7_ROT_RADS);
+        }
+        let mut r = self.r;
+        for _ in 0..res {
+            r *= SQRT7;
+        }
+        let coord = CoordIJK::from_hex2d(Vec2d::new(r * theta.cos(), r * theta.sin()));
+        face_ijk_to_index(FaceIJK::new(self.face, coord), res)
+    }
+}
+
+/// Returns the H3 index of the resolution `res` cell at `fijk`.
+fn face_ijk_to_index(fijk: FaceIJK, res: u8) -> Index {
+    // Mode 1 (cell), all digits set to 7 (unused).
+    let mut idx = Index::from_raw(0x0800_1fff_ffff_ffff).set_res(res);
+
+    // Build the index from finest resolution up, leaving `ijk` as
+    // the base cell's coordinates on this face.
+    let mut ijk = fijk.coord;
+    for r in (1..=res).rev() {
+        let last_ijk = ijk;
+        let last_center = if is_class_iii(r) {
+            ijk = ijk.up_ap7();
+            ijk.down_ap7()
+        } else {
+            ijk = ijk.up_ap7r();
+            ijk.down_ap7r()
+        };
+        let digit = (last_ijk - last_center)
+            .to_digit()
+            .expect("difference from parent center is a unit vector");
+        idx = idx.set_digit(r, digit);
+    }
+
+    let (base, rotations) =
+        FACE_IJK_BASE_CELLS[fijk.face as usize][ijk.i as usize][ijk.j as usize][ijk.k as usize];
+    idx = idx.set_base(base);
+
+    // Rotate into the base cell's canonical orientation.
+    if is_pentagon_base(base) {
+        // Force rotation out of the missing k-axis subsequence.
+        if leading_digit(idx) == Some(K_AXIS_DIGIT) {
+            idx = if is_cw_offset(base, fijk.face) {
+                rotate60_cw(idx)
+            } else {
+                rotate60_ccw(idx)
+            };
+        }
+        for _ in 0..rotations {
+            idx = rotate_pent60_ccw(idx);
+        }
+    } else {
+        for _ in 0..rotations {
+            idx = rotate60_ccw(idx);
+        }
+    }
+    idx
+}
+
+/// Returns `true` if `face` is one of pentagon `base`'s clockwise
+/// offset faces.
+fn is_cw_offset(base: u8, face: u8) -> bool {
+    BASE_CELL_DATA[base as usize]
+        .cw_offset_pent
+        .is_some_and(|(a, b)| a == face || b == face)
+}
+
+/// Returns the first non-zero digit of `idx`, if any.
+fn leading_digit(idx: Index) -> Option<u8> {
+    (1..=idx.res())
+        .filter_map(|res| idx.digit(res))
+        .find(|&digit| digit != 0)
+}
+
+/// Rotates every digit of `idx` 60° counterclockwise.
+fn rotate60_ccw(idx: Index) -> Index {
+    const CCW: [u8; 7] = [0, 5, 3, 1, 6, 4, 2];
+    rotate_digits(idx, &CCW)
+}
+
+/// Rotates every digit of `idx` 60° clockwise.
+fn rotate60_cw(idx: Index) -> Index {
+    const CW: [u8; 7] = [0, 3, 6, 2, 5, 1, 4];
+    rotate_digits(idx, &CW)
+}
+
+fn rotate_digits(mut idx: Index, rotated: &[u8; 7]) -> Index {
+    for res in 1..=idx.res() {
+        let digit = idx.digit(res).expect("res is in [1, 15]");
+        idx = idx.set_digit(res, rotated[digit as usize]);
+    }
+    idx
+}
+
+/// Rotates every digit of pentagonal `idx` 60° counterclockwise,
+/// skipping over the deleted k-axis subsequence.
+fn rotate_pent60_ccw(mut idx: Index) -> Index {
+    let mut found_first_non_zero = false;
+    for res in 1..=idx.res() {
+        let digit = idx.digit(res).expect("res is in [1, 15]");
+        idx = idx.set_digit(res, [0, 5, 3, 1, 6, 4, 2][digit as usize]);
+        if !found_first_non_zero && idx.digit(res) != Some(0) {
+            found_first_non_zero = true;
+            if leading_digit(idx) == Some(K_AXIS_DIGIT) {
+                idx = rotate60_ccw(idx);
+            }
+        }
+    }
+    idx
+}
+
+/// Returns the `FaceIJK` address of `idx`'s center, on the face that
+/// center actually lies on.
+fn index_to_face_ijk(mut idx: Index) -> FaceIJK {
+    let base = idx.base();
+    let pentagon = is_pentagon_base(base);
+    // Adjust for the pentagonal missing sequence; all of sub-sequence
+    // 5 needs to be adjusted (and not just sub-sequence 1).
+    if pentagon && leading_digit(idx) == Some(IK_AXIS_DIGIT) {
+        idx = rotate60_cw(idx);
+    }
+
+    let data = &BASE_CELL_DATA[base as usize];
+    let res = idx.res();
+    let mut fijk = FaceIJK::new(data.home_face, data.home_ijk);
+    for r in 1..=res {
+        fijk.coord = if is_class_iii(r) {
+            fijk.coord.down_ap7()
+        } else {
+            fijk.coord.down_ap7r()
+        };
+        fijk.coord = fijk
+            .coord
+            .neighbor(idx.digit(r).expect("res is in [1, 15]"));
+    }
+
+    // A hexagon centered on its base cell's home face can't overflow
+    // onto another face.
+    if !pentagon && (res == 0 || data.home_ijk == CoordIJK::default()) {
+        return fijk;
+    }
+
+    // Work in Class II to check for overage.
+    let orig_ijk = fijk.coord;
+    let mut class_ii_res = res;
+    if is_class_iii(res) {
+        fijk.coord = fijk.coord.down_ap7r();
+        class_ii_res += 1;
+    }
+
+    let pent_leading_4 = pentagon && leading_digit(idx) == Some(I_AXIS_DIGIT);
+    if adjust_overage_class_ii(&mut fijk, class_ii_res, pent_leading_4, false) != Overage::None {
+        // A pentagon's base cell may need several adjustments.
+        if pentagon {
+            while adjust_overage_class_ii(&mut fijk, class_ii_res, false, false) != Overage::None {}
+        }
+        if class_ii_res != res {
+            fijk.coord = fijk.coord.up_ap7r();
+        }
+    } else if class_ii_res != res {
+        fijk.coord = orig_ijk;
+    }
+    fijk
+}
+
+/// Where a `FaceIJK` lies relative to its face's edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Overage {
+    /// On the face.
+    None,
+    /// Exactly on an edge of the face; only possible on a substrate
+    /// grid.
+    FaceEdge,
+    /// Past the face's edge, and has been moved onto the adjacent face.
+    NewFace,
+}
+
+/// Moves `fijk`, which is at Class II resolution `res`, onto the
+/// adjacent face if it lies past its current face's edge.
+///
+/// `substrate` indicates `fijk` is on the aperture 3 substrate grid
+/// used to address cell vertices.
+fn adjust_overage_class_ii(
+    fijk: &mut FaceIJK,
+    res: u8,
+    pent_leading_4: bool,
+    substrate: bool,
+) -> Overage {
+    let scale = if substrate { 3 } else { 1 };
+    let max_dim = max_dim_by_cii_res(res) * scale;
+    let sum = fijk.coord.sum();
+    if substrate && sum == max_dim {
+        return Overage::FaceEdge;
+    }
+    if sum <= max_dim {
+        return Overage::None;
+    }
+    let quadrant = if fijk.coord.k > 0 {
+        if fijk.coord.j > 0 {
+            JK
+        } else {
+            if pent_leading_4 {
+                // Translate the origin to the center of the pentagon
+                // and rotate to adjust for the missing sequence.
+                let origin = CoordIJK::new(max_dim, 0, 0);
+                fijk.coord = (fijk.coord - origin).rotate60_cw() + origin;
+            }
+            KI
+        }
+    } else {
+        IJ
+    };
+    let orient = FACE_NEIGHBORS[fijk.face as usize][quadrant];
+    fijk.face = orient.face;
+    for _ in 0..orient.ccw_rot60 {
+        fijk.coord = fijk.coord.rotate60_ccw();
+    }
+    fijk.coord = (fijk.coord + orient.translate * (unit_scale_by_cii_res(res) * scale)).normalize();
+
+    // Overage points on pentagon boundaries can end up on edges.
+    if substrate && fijk.coord.sum() == max_dim {
+        Overage::FaceEdge
+    } else {
+        Overage::NewFace
+    }
+}
+
+/// Returns the (lat, lng) radians of the point at `v` on `face`'s
+/// resolution `res` grid.
+///
+/// If `substrate` is set, `v` is on the aperture 3 substrate grid of
+/// Class II resolution `res`, which is already aligned with the
+/// icosahedron.
+fn hex2d_to_geo(v: Vec2d, face: u8, res: u8, substrate: bool) -> (f64, f64) {
+    let mut r = v.magnitude();
+    if r < EPSILON {
+        return FACE_CENTER_GEO[face as usize];
+    }
+    let mut theta = v.y.atan2(v.x);
+
+    // Scale for the current resolution length, then back to a unit
+    // gnomonic distance.
+    for _ in 0..res {
+        r /= SQRT7;
+    }
+    if substrate {
+        r /= 3.0;
+        if is_class_iii(res) {
+            r /= SQRT7;
+        }
+    }
+    r = (r * RES0_U_GNOMONIC).atan();
+
+    // Adjust theta for Class III.
+    if !substrate && is_class_iii(res) {
+        theta = pos_angle(theta + AP7_ROT_RADS);
+    }
+    theta = pos_angle(FACE_AXES_AZ_RADS_CII[face as usize][0] - theta);
+    az_distance(FACE_CENTER_GEO[face as usize], theta, r)
+}
+
+/// Returns the (lat, lng) degrees of `idx`'s center.
+pub(crate) fn index_to_latlng(idx: Index) -> (f64, f64) {
+    let fijk = index_to_face_ijk(idx);
+    let (lat, lng) = hex2d_to_geo(fijk.coord.to_hex2d(), fijk.face, idx.res(), false);
+    (lat.to_degrees(), lng.to_degrees())
+}
+
+/// A point on a cell's boundary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct BoundaryVertex {
+    /// (lat, lng) degrees.
+    pub(crate) latlng: (f64, f64),
+    /// `false` if this point isn't one of the cell's corners, but
+    /// where one of its edges crosses an icosahedron edge.
+    pub(crate) topological: bool,
+}
+
+/// Returns the boundary of `idx`, counterclockwise.
+///
+/// Besides a hexagon's 6 or a pentagon's 5 corners, the boundary of a
+/// Class III cell includes the points where its edges cross
+/// icosahedron edges, as each face has its own projection and edges
+/// are only straight within one face.
+pub(crate) fn index_to_boundary(idx: Index) -> Vec<BoundaryVertex> {
+    let fijk = index_to_face_ijk(idx);
+    let res = idx.res();
+    let pentagon = is_pentagon_base(idx.base()) && (1..=res).all(|r| idx.digit(r) == Some(0));
+    let mut boundary = Vec::with_capacity(10);
+    let mut push = |v: Vec2d, face: u8, res: u8, topological: bool| {
+        let (lat, lng) = hex2d_to_geo(v, face, res, true);
+        boundary.push(BoundaryVertex {
+            latlng: (lat.to_degrees(), lng.to_degrees()),
+            topological,
+        });
+    };
+    let (verts, adj_res) = substrate_verts(fijk, res);
+
+    if pentagon {
+        // Loop once more to check the last edge for a crossing.
+        let mut last: Option<FaceIJK> = None;
+        for vert in 0..=5 {
+            let mut vfijk = verts[vert % 5];
+            while adjust_overage_class_ii(&mut vfijk, adj_res, false, true) == Overage::NewFace {}
+
+            // Every Class III pentagon edge crosses an icosahedron
+            // edge.
+            if let (true, Some(last)) = (is_class_iii(res), last) {
+                // Express this vertex in the last vertex's face's
+                // coordinates.
+                let dir = adjacent_face_dir(vfijk.face, last.face)
+                    .expect("pentagon vertices are on adjacent faces");
+                let orient = FACE_NEIGHBORS[vfijk.face as usize][dir];
+                let mut ijk = vfijk.coord;
+                for _ in 0..orient.ccw_rot60 {
+                    ijk = ijk.rotate60_ccw();
+                }
+                ijk = (ijk + orient.translate * (unit_scale_by_cii_res(adj_res) * 3)).normalize();
+
+                let (edge0, edge1) = face_edge(orient.face, vfijk.face, adj_res);
+                let inter = Vec2d::intersect(last.coord.to_hex2d(), ijk.to_hex2d(), edge0, edge1);
+                push(inter, orient.face, adj_res, false);
+            }
+            if vert < 5 {
+                push(vfijk.coord.to_hex2d(), vfijk.face, adj_res, true);
+            }
+            last = Some(vfijk);
+        }
+    } else {
+        // Loop once more to check the last edge for a crossing.
+        let mut last: Option<(u8, Overage)> = None;
+        for vert in 0..=6 {
+            let v = vert % 6;
+            let mut vfijk = verts[v];
+            let overage = adjust_overage_class_ii(&mut vfijk, adj_res, false, true);
+
+            // Class II cell edges are aligned with icosahedron edges,
+            // so only Class III edges can cross them.
+            if let (true, Some((last_face, last_overage))) = (is_class_iii(res), last) {
+                if vfijk.face != last_face && last_overage != Overage::FaceEdge {
+                    let orig0 = verts[(v + 5) % 6].coord.to_hex2d();
+                    let orig1 = verts[v].coord.to_hex2d();
+                    let adjacent = if last_face == fijk.face {
+                        vfijk.face
+                    } else {
+                        last_face
+                    };
+                    let (edge0, edge1) = face_edge(fijk.face, adjacent, adj_res);
+                    let inter = Vec2d::intersect(orig0, orig1, edge0, edge1);
+                    // A crossing at a corner leaves both adjacent
+                    // edges on a single face, so needs no extra point.
+                    if !inter.almost_eq(orig0) && !inter.almost_eq(orig1) {
+                        push(inter, fijk.face, adj_res, false);
+                    }
+                }
+            }
+            if vert < 6 {
+                push(vfijk.coord.to_hex2d(), vfijk.face, adj_res, true);
+            }
+            last = Some((vfijk.face, overage));
+        }
+    }
+    boundary
+}
+
+/// Returns the corners of the cell at `fijk` on the Class II aperture
+/// 3 substrate grid, along with that grid's resolution.
+fn substrate_verts(fijk: FaceIJK, res: u8) -> ([FaceIJK; 6], u8) {
+    // Corners of an origin-centered cell on an aperture 33r substrate
+    // grid, counterclockwise from the i-axis.
+    const VERTS_CII: [CoordIJK; 6] = [
+        CoordIJK::new(2, 1, 0),
+        CoordIJK::new(1, 2, 0),
+        CoordIJK::new(0, 2, 1),
+        CoordIJK::new(0, 1, 2),
+        CoordIJK::new(1, 0, 2),
+        CoordIJK::new(2, 0, 1),
+    ];
+    const VERTS_CIII: [CoordIJK; 6] = [
+        CoordIJK::new(5, 4, 0),
+        CoordIJK::new(1, 5, 0),
+        CoordIJK::new(0, 5, 4),
+        CoordIJK::new(0, 1, 5),
+        CoordIJK::new(4, 0, 5),
+        CoordIJK::new(5, 0, 1),
+    ];
+
+    let mut center = fijk.coord.down_ap3().down_ap3r();
+    let (verts, adj_res) = if is_class_iii(res) {
+        // Add a clockwise aperture 7 to get back to Class II.
+        center = center.down_ap7r();
+        (&VERTS_CIII, res + 1)
+    } else {
+        (&VERTS_CII, res)
+    };
+    let mut out = [FaceIJK::default(); 6];
+    for (out, vert) in out.iter_mut().zip(verts) {
+        *out = FaceIJK::new(fijk.face, (center + *vert).normalize());
+    }
+    (out, adj_res)
+}
+
+/// Returns the index into `FACE_NEIGHBORS[face]` of `adjacent`, or
+/// `None` if the faces don't touch.
+fn adjacent_face_dir(face: u8, adjacent: u8) -> Option<usize> {
+    FACE_NEIGHBORS[face as usize]
+        .iter()
+        .position(|orient| orient.face == adjacent)
+}
+
+/// Returns the endpoints of the edge between `face` and `adjacent` on
+/// `face`'s Class II resolution `res` substrate grid.
+fn face_edge(face: u8, adjacent: u8, res: u8) -> (Vec2d, Vec2d) {
+    let max_dim = f64::from(max_dim_by_cii_res(res));
+    let v0 = Vec2d::new(3.0 * max_dim, 0.0);
+    let v1 = Vec2d::new(-1.5 * max_dim, 3.0 * SQRT3_2 * max_dim);
+    let v2 = Vec2d::new(-1.5 * max_dim, -3.0 * SQRT3_2 * max_dim);
+    match adjacent_face_dir(face, adjacent) {
+        Some(IJ) => (v0, v1),
+        Some(JK) => (v1, v2),
+        _ => (v2, v0),
+    }
+}
+
+/// Returns `lng` shifted by a multiple of 360° to within 180° of
+/// `prev`.
+fn unwrap_lng(prev: f64, lng: f64) -> f64 {
+    lng + 360.0 * ((prev - lng) / 360.0).round()
+}
+
+/// Returns `cell`'s boundary as (lng, lat) degrees, unwrapped to not
+/// jump across the antimeridian.
+pub(crate) fn boundary_coords(cell: Cell) -> Vec<Coord<f64>> {
+    let mut ring: Vec<Coord<f64>> = Vec::with_capacity(10);
+    for (lat, lng) in cell.boundary() {
+        let x = ring.last().map_or(lng, |prev| unwrap_lng(prev.x, lng));
+        ring.push(Coord { x, y: lat });
+    }
+    ring
+}
+
+/// Returns `true` if `point` is inside `ring`, which may or may not
+/// repeat its first coordinate at the end.
+fn ring_contains(ring: &[Coord<f64>], point: Coord<f64>) -> bool {
+    let mut inside = false;
+    for (i, a) in ring.iter().enumerate() {
+        let b = ring[(i + 1) % ring.len()];
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cell;
+    use std::convert::TryFrom;
+
+    /// Yields a grid of points spanning the globe, nudged off round
+    /// numbers so they don't all land on cell edges.
+    fn points() -> impl Iterator<Item = (f64, f64)> {
+        (-89..90)
+            .step_by(4)
+            .flat_map(|lat| (-180..180).step_by(7).map(move |lng| (lat, lng)))
+            .map(|(lat, lng)| (f64::from(lat) + 0.123_456, f64::from(lng) + 0.654_321))
+    }
+
+    #[test]
+    fn test_from_latlng_matches_h3o() {
+        for (lat, lng) in points() {
+            let latlng = h3o::LatLng::new(lat, lng).unwrap();
+            for res in 0..=15 {
+                let expected = latlng.to_cell(h3o::Resolution::try_from(res).unwrap());
+                let cell = Cell::from_latlng(lat, lng, res).unwrap();
+                assert_eq!(
+                    cell.into_raw(),
+                    u64::from(expected),
+                    "{} {} {}",
+                    lat,
+                    lng,
+                    res
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_latlng_matches_h3o() {
+        let pentagons = h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+            .flat_map(|res| res.pentagons());
+        let cells = points()
+            .flat_map(|(lat, lng)| {
+                let latlng = h3o::LatLng::new(lat, lng).unwrap();
+                h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+                    .map(move |res| latlng.to_cell(res))
+            })
+            .chain(pentagons);
+        for cellidx in cells {
+            let expected = h3o::LatLng::from(cellidx);
+            let cell = Cell::from_raw(u64::from(cellidx)).unwrap();
+            let (lat, lng) = cell.to_latlng();
+            assert!(
+                (lat - expected.lat()).abs() < 1e-9 && (lng - expected.lng()).abs() < 1e-9,
+                "{} ({}, {}) != ({}, {})",
+                cell,
+                lat,
+                lng,
+                expected.lat(),
+                expected.lng()
+            );
+            assert_eq!(Cell::from_latlng(lat, lng, cell.res()).unwrap(), cell);
+        }
+    }
+
+    #[test]
+    fn test_boundary_matches_h3o() {
+        let pentagons = h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+            .flat_map(|res| res.pentagons());
+        let cells = points()
+            .flat_map(|(lat, lng)| {
+                let latlng = h3o::LatLng::new(lat, lng).unwrap();
+                h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+                    .map(move |res| latlng.to_cell(res))
+            })
+            .chain(pentagons);
+        for cellidx in cells {
+            let expected = cellidx.boundary();
+            let cell = Cell::from_raw(u64::from(cellidx)).unwrap();
+            let boundary = cell.boundary();
+            assert_eq!(boundary.len(), expected.len(), "{}", cell);
+            for ((lat, lng), expected) in boundary.into_iter().zip(expected.iter()) {
+                assert!(
+                    (lat - expected.lat()).abs() < 1e-9 && (lng - expected.lng()).abs() < 1e-9,
+                    "{} ({}, {}) != ({}, {})",
+                    cell,
+                    lat,
+                    lng,
+                    expected.lat(),
+                    expected.lng()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_matches_h3o() {
+        use std::collections::HashSet;
+        let near_pentagons =
+            h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+                .flat_map(|res| res.pentagons())
+                .flat_map(|pentagon| pentagon.grid_disk::<Vec<_>>(2));
+        let cells = points()
+            .flat_map(|(lat, lng)| {
+                let latlng = h3o::LatLng::new(lat, lng).unwrap();
+                h3o::Resolution::range(h3o::Resolution::Zero, h3o::Resolution::Fifteen)
+                    .map(move |res| latlng.to_cell(res))
+            })
+            .chain(near_pentagons);
+        let to_cell = |cellidx: h3o::CellIndex| Cell::from_raw(u64::from(cellidx)).unwrap();
+        for cellidx in cells {
+            let cell = to_cell(cellidx);
+            let expected: HashSet<Cell> = cellidx
+                .grid_disk::<Vec<_>>(1)
+                .into_iter()
+                .map(to_cell)
+                .filter(|&other| other != cell)
+                .collect();
+            assert_eq!(
+                cell.neighbors().into_iter().collect::<HashSet<_>>(),
+                expected,
+                "{}",
+                cell
+            );
+
+            let distances = cellidx.grid_disk_distances::<Vec<_>>(3);
+            let disk = cell.grid_disk(3);
+            assert_eq!(disk.len(), distances.len(), "{}", cell);
+            assert_eq!(
+                disk.iter().copied().collect::<HashSet<_>>(),
+                distances.iter().map(|&(other, _)| to_cell(other)).collect(),
+                "{}",
+                cell
+            );
+            for k in 0..=3 {
+                assert_eq!(
+                    cell.grid_ring(k).into_iter().collect::<HashSet<_>>(),
+                    distances
+                        .iter()
+                        .filter(|&&(_, distance)| distance == k)
+                        .map(|&(other, _)| to_cell(other))
+                        .collect(),
+                    "{} {}",
+                    cell,
+                    k
+                );
+            }
+            for (other, _) in distances {
+                assert_eq!(
+                    cell.grid_distance(&to_cell(other)),
+                    cellidx.grid_distance(other).ok().map(|d| d as u32),
+                    "{} {}",
+                    cell,
+                    to_cell(other)
+                );
+            }
+
+            // Farther away, possibly across base cells.
+            let center = h3o::LatLng::from(cellidx);
+            let far = h3o::LatLng::new(center.lat() + 1.5, center.lng() + 2.5)
+                .unwrap()
+                .to_cell(cellidx.resolution());
+            assert_eq!(
+                cell.grid_distance(&to_cell(far)),
+                cellidx.grid_distance(far).ok().map(|d| d as u32),
+                "{} {}",
+                cell,
+                to_cell(far)
+            );
+        }
+    }
+
+    #[test]
+    fn test_grid_ring_matches_disk() {
+        use std::collections::HashSet;
+        let cells = [
+            Cell::from_raw(0x8c3969a41da15ff).unwrap(),
+            // On the edge of a base cell.
+            Cell::from_raw(0x8009fffffffffff)
+                .unwrap()
+                .center_child(1)
+                .unwrap()
+                .neighbors()[1]
+                .center_child(4)
+                .unwrap(),
+            // Near a pentagon.
+            Cell::from_raw(0x8009fffffffffff)
+                .unwrap()
+                .center_child(5)
+                .unwrap(),
+        ];
+        for cell in cells.iter() {
+            for k in (0..=30).step_by(3) {
+                let expected: HashSet<Cell> = super::Disk::new(*cell, k)
+                    .filter(|&(_, distance)| distance == k)
+                    .map(|(cell, _)| cell)
+                    .collect();
+                let ring = cell.grid_ring(k);
+                assert_eq!(ring.len(), expected.len(), "{} {}", cell, k);
+                assert_eq!(
+                    ring.into_iter().collect::<HashSet<_>>(),
+                    expected,
+                    "{} {}",
+                    cell,
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_latlng_invalid() {
+        assert!(Cell::from_latlng(f64::NAN, 0.0, 5).is_err());
+        assert!(Cell::from_latlng(0.0, f64::INFINITY, 5).is_err());
+        assert!(Cell::from_latlng(0.0, 0.0, 16).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Finding the leaves nearest to a cell.
+//!
+//! Rather than expanding rings outward from the cell, which visits
+//! every cell closer than the answer, this is a best-first search
+//! over the tree. Each node is queued by a lower bound on how far its
+//! descendants can be, found at the node's own resolution, so empty
+//! base cells and subtrees are never visited, and a subtree is only
+//! descended into while it could still hold one of the nearest
+//! leaves. Coarse leaves are descended into the same way, down to the
+//! cell's resolution, to find their nearest part.
+//!
+//! Grid distances can't be measured more than a base cell away or
+//! across a pentagon, so there the search falls back to the great
+//! circle distance between cell centers, in steps of the cell's
+//! distance to its neighbors.
+
+use super::{great_circle_distance, grid::distance_bound, SQRT7};
+use crate::{cell::CellStack, hex_tree_map::Distance, iteration::Iter, node::Node, Cell};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+};
+
+/// Returns up to `k` leaves in `nodes` nearest to `origin`, with
+/// their distance in steps of `origin`'s resolution, nearest first.
+pub(crate) fn k_nearest<V>(
+    nodes: &[Option<Box<Node<V>>>],
+    origin: Cell,
+    k: usize,
+) -> Vec<(Cell, &V, Distance)> {
+    let origin = Origin::new(origin);
+    let mut queue = BinaryHeap::new();
+    for (base, node) in nodes.iter().enumerate() {
+        if let Some(node) = node.as_deref() {
+            let mut cell_stack = CellStack::new();
+            cell_stack.push(base as u8);
+            let cell = *cell_stack.cell().expect("just pushed");
+            push(&mut queue, &origin, cell, Candidate::Node(node));
+        }
+    }
+
+    let mut nearest = Vec::new();
+    // Coarse leaves found through one of their parts, which may have
+    // other parts still queued.
+    let mut found = HashSet::new();
+    while nearest.len() < k {
+        let Entry {
+            distance,
+            exact,
+            cell,
+            candidate,
+        } = match queue.pop() {
+            Some(entry) => entry,
+            None => break,
+        };
+        match candidate {
+            Candidate::Node(Node::Leaf(value)) if exact => nearest.push((cell, value, distance)),
+            Candidate::Node(Node::Leaf(value)) => {
+                push_parts(&mut queue, &origin, cell, cell, value);
+            }
+            Candidate::Node(Node::Parent(children)) if exact => {
+                let leaves = Iter::new(children, CellStack::from(cell));
+                nearest.extend(
+                    leaves
+                        .take(k - nearest.len())
+                        .map(|(leaf, value)| (leaf, value, distance)),
+                );
+            }
+            Candidate::Node(Node::Parent(children)) => {
+                for (digit, child) in children.iter().enumerate() {
+                    if let Some(child) = child.as_deref() {
+                        let mut cell_stack = CellStack::from(cell);
+                        cell_stack.push(digit as u8);
+                        let child_cell = *cell_stack.cell().expect("just pushed");
+                        push(&mut queue, &origin, child_cell, Candidate::Node(child));
+                    }
+                }
+            }
+            Candidate::Part(leaf, _) if found.contains(&leaf) => (),
+            Candidate::Part(leaf, value) if exact => {
+                found.insert(leaf);
+                nearest.push((leaf, value, distance));
+            }
+            Candidate::Part(leaf, value) => {
+                push_parts(&mut queue, &origin, cell, leaf, value);
+            }
+        }
+    }
+    nearest
+}
+
+/// Queues `candidate` at `cell`.
+fn push<'a, V>(
+    queue: &mut BinaryHeap<Entry<'a, V>>,
+    origin: &Origin,
+    cell: Cell,
+    candidate: Candidate<'a, V>,
+) {
+    let (distance, exact) = origin.distance(cell);
+    queue.push(Entry {
+        distance,
+        exact,
+        cell,
+        candidate,
+    });
+}
+
+/// Queues every child of `cell`, which is part of the coarse `leaf`
+/// holding `value`.
+fn push_parts<'a, V>(
+    queue: &mut BinaryHeap<Entry<'a, V>>,
+    origin: &Origin,
+    cell: Cell,
+    leaf: Cell,
+    value: &'a V,
+) {
+    for child in cell.children(cell.res() + 1) {
+        push(queue, origin, child, Candidate::Part(leaf, value));
+    }
+}
+
+/// The cell whose nearest leaves are being searched for.
+struct Origin {
+    cell: Cell,
+    /// `cell`'s center in (lat, lng) radians.
+    center: (f64, f64),
+    /// Great circle distance in radians from `cell`'s center to a
+    /// neighbor's.
+    step: f64,
+}
+
+impl Origin {
+    fn new(cell: Cell) -> Self {
+        let center = to_radians(cell.to_latlng());
+        let neighbor = cell.neighbors()[0];
+        let step = great_circle_distance(center, to_radians(neighbor.to_latlng()));
+        Self { cell, center, step }
+    }
+
+    /// Returns how far `cell`'s nearest descendant at this origin's
+    /// resolution is, and whether that is the distance itself rather
+    /// than a lower bound, as by [`distance_bound`].
+    ///
+    /// Where that can't be measured, the distance is instead
+    /// estimated in steps from the great circle distance between
+    /// centers. Descendants' centers are within about 0.6 of a step of
+    /// `cell`'s own resolution from its center, so allowing 2 of
+    /// those steps keeps the bound below the estimate for any of them
+    /// despite the grid's distortion.
+    fn distance(&self, cell: Cell) -> (Distance, bool) {
+        if let Some((distance, exact)) = distance_bound(self.cell, cell) {
+            return (Distance::Exact(distance), exact);
+        }
+        let res = self.cell.res();
+        let steps = |other: Cell| {
+            great_circle_distance(self.center, to_radians(other.to_latlng())) / self.step
+        };
+        match cell.to_parent(res) {
+            Some(ancestor) => (Distance::Estimated(steps(ancestor).round() as u32), true),
+            None => {
+                let reach = 2.0 * SQRT7.powi(i32::from(res - cell.res()));
+                let bound = (steps(cell) - reach).max(0.0).floor() as u32;
+                (Distance::Estimated(bound), false)
+            }
+        }
+    }
+}
+
+/// Converts (lat, lng) degrees to radians.
+fn to_radians((lat, lng): (f64, f64)) -> (f64, f64) {
+    (lat.to_radians(), lng.to_radians())
+}
+
+/// Something to search for the nearest leaves.
+enum Candidate<'a, V> {
+    /// A node of the tree.
+    Node(&'a Node<V>),
+    /// Part of the coarse leaf at the given cell, holding the given
+    /// value.
+    Part(Cell, &'a V),
+}
+
+/// A queued [`Candidate`], ordered so a [`BinaryHeap`] pops the
+/// nearest first.
+struct Entry<'a, V> {
+    /// How far the candidate's nearest descendant is, or a lower
+    /// bound on it if not `exact`, whether measured or estimated.
+    distance: Distance,
+    exact: bool,
+    cell: Cell,
+    candidate: Candidate<'a, V>,
+}
+
+impl<'a, V> Entry<'a, V> {
+    /// Nearest first, then in [`Cell`] order. A node's descendants
+    /// sort right before it, so leaves at the same distance are found
+    /// in `Cell` order too.
+    fn key(&self) -> (u32, Cell) {
+        (self.distance.rings(), self.cell)
+    }
+}
+
+impl<'a, V> PartialEq for Entry<'a, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<'a, V> Eq for Entry<'a, V> {}
+
+impl<'a, V> PartialOrd for Entry<'a, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, V> Ord for Entry<'a, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key().cmp(&self.key())
+    }
+}
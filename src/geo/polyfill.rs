@@ -0,0 +1,265 @@
+//! Covering polygons with cells.
+//!
+//! Cells are refined from resolution 0 down, keeping a cell whole as
+//! soon as all of its descendants are known to be inside the polygon
+//! and dropping it as soon as none of them can touch it, so only cells
+//! along the polygon's boundary are ever subdivided to the target
+//! resolution. Like H3, polygon edges are straight lines in lat/lng
+//! space.
+
+use super::{boundary_coords, ring_contains, unwrap_lng};
+use crate::{Cell, Error, Result};
+use geo_types::{Coord, LineString, Polygon};
+
+/// How much to grow a cell's bounding box so it also bounds all of
+/// its descendants, which stick out past the cell's own boundary.
+///
+/// This is the factor H3 found empirically to cover all children.
+const CHILD_SCALE_FACTOR: f64 = 1.4;
+
+/// Which cells at the target resolution are considered part of a
+/// polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContainmentMode {
+    /// Cells whose center is inside the polygon.
+    ///
+    /// Adjacent polygons covered in this mode never share a cell, and
+    /// this is what H3's `polygonToCells` does.
+    ContainsCentroid,
+    /// Cells entirely inside the polygon.
+    ContainsBoundary,
+    /// Cells which overlap the polygon at all.
+    IntersectsBoundary,
+}
+
+/// Calls `emit` with the fewest cells, none finer than `res`, which
+/// cover `polygon` according to `mode`.
+pub(crate) fn polyfill<F>(
+    polygon: &Polygon<f64>,
+    res: u8,
+    mode: ContainmentMode,
+    mut emit: F,
+) -> Result<()>
+where
+    F: FnMut(Cell),
+{
+    if res > 15 {
+        return Err(Error::Resolution(res));
+    }
+    let rings = std::iter::once(polygon.exterior()).chain(polygon.interiors());
+    if let Some(coord) = rings
+        .flat_map(|ring| ring.0.iter())
+        .find(|coord| !coord.x.is_finite() || !coord.y.is_finite())
+    {
+        return Err(Error::LatLng(coord.y, coord.x));
+    }
+    if polygon.exterior().0.is_empty() {
+        return Ok(());
+    }
+    let polygon = Shape::new(polygon);
+
+    let mut stack = (0..122_u64)
+        .map(|base| Cell(0x0800_1fff_ffff_ffff | base << 45))
+        .collect::<Vec<_>>();
+    while let Some(cell) = stack.pop() {
+        if cell.res() == res {
+            let covered = match mode {
+                ContainmentMode::ContainsCentroid => {
+                    let (lat, lng) = cell.to_latlng();
+                    polygon.contains(Coord { x: lng, y: lat })
+                }
+                ContainmentMode::ContainsBoundary => {
+                    polygon.relate(&boundary_coords(cell)) == Relation::Inside
+                }
+                ContainmentMode::IntersectsBoundary => {
+                    polygon.relate(&boundary_coords(cell)) != Relation::Outside
+                }
+            };
+            if covered {
+                emit(cell);
+            }
+        } else {
+            let relation = relate_descendants(&polygon, cell);
+            match relation {
+                Relation::Inside => emit(cell),
+                Relation::Outside => (),
+                Relation::Overlaps => stack.extend(cell.children(cell.res() + 1)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns where all of `cell`'s descendants lie relative to
+/// `polygon`.
+fn relate_descendants(polygon: &Shape<'_>, cell: Cell) -> Relation {
+    let ring = boundary_coords(cell);
+    let (mut min, mut max) = (ring[0], ring[0]);
+    for coord in &ring {
+        min = Coord {
+            x: min.x.min(coord.x),
+            y: min.y.min(coord.y),
+        };
+        max = Coord {
+            x: max.x.max(coord.x),
+            y: max.y.max(coord.y),
+        };
+    }
+    let center = Coord {
+        x: (min.x + max.x) / 2.0,
+        y: (min.y + max.y) / 2.0,
+    };
+    let half_width = (max.x - min.x) / 2.0 * CHILD_SCALE_FACTOR;
+    let half_height = (max.y - min.y) / 2.0 * CHILD_SCALE_FACTOR;
+    let (mut min, mut max) = (
+        Coord {
+            x: center.x - half_width,
+            y: (center.y - half_height).max(-90.0),
+        },
+        Coord {
+            x: center.x + half_width,
+            y: (center.y + half_height).min(90.0),
+        },
+    );
+
+    // An unwrapped ring around a pole doesn't close, but ends a full
+    // turn from where it started.
+    let last = ring[ring.len() - 1];
+    let winding = unwrap_lng(ring[0].x, last.x) - ring[0].x;
+    if winding.abs() > 180.0 {
+        min.x = -180.0;
+        max.x = 180.0;
+        if center.y > 0.0 {
+            max.y = 90.0;
+        } else {
+            min.y = -90.0;
+        }
+    }
+
+    let relate = |shift: f64| {
+        polygon.relate(&[
+            Coord {
+                x: min.x + shift,
+                y: min.y,
+            },
+            Coord {
+                x: max.x + shift,
+                y: min.y,
+            },
+            Coord {
+                x: max.x + shift,
+                y: max.y,
+            },
+            Coord {
+                x: min.x + shift,
+                y: max.y,
+            },
+        ])
+    };
+    if min.x >= -180.0 && max.x <= 180.0 {
+        return relate(0.0);
+    }
+    // The polygon may be on either side of the antimeridian, but
+    // doesn't cross it, so can't have these descendants all inside.
+    let shift = if max.x > 180.0 { -360.0 } else { 360.0 };
+    match (relate(0.0), relate(shift)) {
+        (Relation::Outside, Relation::Outside) => Relation::Outside,
+        _ => Relation::Overlaps,
+    }
+}
+
+/// Where a ring lies relative to a polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Relation {
+    Inside,
+    Outside,
+    Overlaps,
+}
+
+/// A polygon along with its bounding box.
+struct Shape<'a> {
+    polygon: &'a Polygon<f64>,
+    min: Coord<f64>,
+    max: Coord<f64>,
+}
+
+impl<'a> Shape<'a> {
+    fn new(polygon: &'a Polygon<f64>) -> Self {
+        let (mut min, mut max) = (polygon.exterior().0[0], polygon.exterior().0[0]);
+        for coord in &polygon.exterior().0 {
+            min = Coord {
+                x: min.x.min(coord.x),
+                y: min.y.min(coord.y),
+            };
+            max = Coord {
+                x: max.x.max(coord.x),
+                y: max.y.max(coord.y),
+            };
+        }
+        Self { polygon, min, max }
+    }
+
+    fn rings(&self) -> impl Iterator<Item = &LineString<f64>> {
+        std::iter::once(self.polygon.exterior()).chain(self.polygon.interiors())
+    }
+
+    /// Returns `true` if `point` is inside the polygon.
+    fn contains(&self, point: Coord<f64>) -> bool {
+        self.rings()
+            .filter(|ring| ring_contains(&ring.0, point))
+            .count()
+            % 2
+            == 1
+    }
+
+    /// Returns where the closed `ring` lies relative to the polygon.
+    fn relate(&self, ring: &[Coord<f64>]) -> Relation {
+        let outside_bbox = ring.iter().all(|c| c.x < self.min.x)
+            || ring.iter().all(|c| c.x > self.max.x)
+            || ring.iter().all(|c| c.y < self.min.y)
+            || ring.iter().all(|c| c.y > self.max.y);
+        if outside_bbox {
+            return Relation::Outside;
+        }
+        for polygon_ring in self.rings() {
+            for line in polygon_ring.lines() {
+                for (i, &a) in ring.iter().enumerate() {
+                    let b = ring[(i + 1) % ring.len()];
+                    if segments_intersect(line.start, line.end, a, b) {
+                        return Relation::Overlaps;
+                    }
+                }
+            }
+            // With no edges crossing, a polygon ring is either
+            // entirely within `ring` or entirely outside of it.
+            if ring_contains(ring, polygon_ring.0[0]) {
+                return Relation::Overlaps;
+            }
+        }
+        if self.contains(ring[0]) {
+            Relation::Inside
+        } else {
+            Relation::Outside
+        }
+    }
+}
+
+/// Returns `true` if segments `p0`-`p1` and `q0`-`q1` touch.
+fn segments_intersect(p0: Coord<f64>, p1: Coord<f64>, q0: Coord<f64>, q1: Coord<f64>) -> bool {
+    let orient = |a: Coord<f64>, b: Coord<f64>, c: Coord<f64>| {
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    };
+    let on_segment = |a: Coord<f64>, b: Coord<f64>, c: Coord<f64>| {
+        c.x >= a.x.min(b.x) && c.x <= a.x.max(b.x) && c.y >= a.y.min(b.y) && c.y <= a.y.max(b.y)
+    };
+    let (d1, d2) = (orient(q0, q1, p0), orient(q0, q1, p1));
+    let (d3, d4) = (orient(p0, p1, q0), orient(p0, p1, q1));
+    let straddles = |a: f64, b: f64| (a > 0.0 && b < 0.0) || (a < 0.0 && b > 0.0);
+    if straddles(d1, d2) && straddles(d3, d4) {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(q0, q1, p0))
+        || (d2 == 0.0 && on_segment(q0, q1, p1))
+        || (d3 == 0.0 && on_segment(p0, p1, q0))
+        || (d4 == 0.0 && on_segment(p0, p1, q1))
+}
@@ -0,0 +1,359 @@
+//! Reading and writing GeoJSON.
+
+use crate::{
+    compaction::ContextCompactor, hex_tree_map::InsertPolicy, Cell, ContainmentMode, Error,
+    HexTreeMap, Result,
+};
+use geojson::{Feature, FeatureCollection, Geometry, JsonValue, Value};
+use std::convert::TryFrom;
+
+impl<V: Clone, C: ContextCompactor<V>> HexTreeMap<V, C> {
+    /// Inserts the cells covering each feature in `features`.
+    ///
+    /// Each feature's geometry must be a `Polygon` or `MultiPolygon`,
+    /// which is covered as by [`HexTreeSet::from_polygon`] with cells
+    /// no finer than `res`. `value` returns the value for a feature's
+    /// cells, usually taken from one of its properties, or `None` to
+    /// skip the feature. Where features overlap, the later one's value
+    /// wins: any coarser leaf an earlier feature left over one of its
+    /// cells is split, as by [`InsertPolicy::Split`], so only the
+    /// overlapping part is replaced.
+    ///
+    /// [`HexTreeSet::from_polygon`]: crate::HexTreeSet::from_polygon
+    /// [`InsertPolicy::Split`]: crate::hex_tree_map::InsertPolicy::Split
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::GeoJson`] if a feature's geometry is not a
+    /// `Polygon` or `MultiPolygon`, or any error
+    /// [`HexTreeSet::from_polygon`] returns. Features before the
+    /// failing one will have been inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{compaction::EqCompactor, ContainmentMode, HexTreeMap};
+    ///
+    /// let features = r#"{
+    ///     "type": "FeatureCollection",
+    ///     "features": [{
+    ///         "type": "Feature",
+    ///         "properties": { "name": "Monaco" },
+    ///         "geometry": {
+    ///             "type": "Polygon",
+    ///             "coordinates": [[
+    ///                 [7.4091, 43.7247], [7.4396, 43.7476], [7.4309, 43.7578], [7.4091, 43.7247]
+    ///             ]]
+    ///         }
+    ///     }]
+    /// }"#
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// let mut map = HexTreeMap::with_compactor(EqCompactor);
+    /// map.extend_from_geojson(&features, 12, ContainmentMode::ContainsCentroid, |feature| {
+    ///     feature.property("name")?.as_str().map(String::from)
+    /// })?;
+    /// assert_eq!(map.get_point(43.7425, 7.4300).unwrap().1, "Monaco");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extend_from_geojson<F>(
+        &mut self,
+        features: &FeatureCollection,
+        res: u8,
+        mode: ContainmentMode,
+        mut value: F,
+    ) -> Result
+    where
+        F: FnMut(&Feature) -> Option<V>,
+    {
+        for feature in &features.features {
+            let (geometry, value) = match (&feature.geometry, value(feature)) {
+                (Some(geometry), Some(value)) => (geometry, value),
+                _ => continue,
+            };
+            for polygon in polygons(geometry)? {
+                crate::geo::polyfill(&polygon, res, mode, |cell| {
+                    self.insert_with_policy(cell, value.clone(), InsertPolicy::Split)
+                        .expect("splitting never fails");
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V, C> HexTreeMap<V, C> {
+    /// Returns a feature for each leaf in this map, in iteration
+    /// order.
+    ///
+    /// Each feature's geometry is its cell's boundary, and it has two
+    /// properties: `h3`, the cell's hexadecimal index, and `value`,
+    /// the result of calling `value` with the leaf's value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{geojson::JsonValue, Cell, HexTreeMap};
+    ///
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(Cell::from_raw(0x8c3969a41da15ff)?, 7);
+    ///
+    /// let features = map.to_geojson_cells(|&v| JsonValue::from(v));
+    /// assert_eq!(features.features.len(), 1);
+    /// let feature = &features.features[0];
+    /// assert_eq!(feature.property("h3"), Some(&JsonValue::from("8c3969a41da15ff")));
+    /// assert_eq!(feature.property("value"), Some(&JsonValue::from(7)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_geojson_cells<F>(&self, mut value: F) -> FeatureCollection
+    where
+        F: FnMut(&V) -> JsonValue,
+    {
+        self.iter()
+            .map(|(cell, cell_value)| {
+                let mut ring = crate::geo::boundary_coords(cell)
+                    .into_iter()
+                    .map(|coord| vec![coord.x, coord.y])
+                    .collect::<Vec<_>>();
+                ring.push(ring[0].clone());
+                let mut feature = Feature::from(Geometry::new(Value::Polygon(vec![ring])));
+                feature.set_property("h3", cell.to_string());
+                feature.set_property("value", value(cell_value));
+                feature
+            })
+            .collect()
+    }
+
+    /// Returns a feature for each distinct value in this map, in order
+    /// of first appearance, outlining all the cells with that value.
+    ///
+    /// Each feature's geometry is a `MultiPolygon`, dissolved as by
+    /// [`HexTreeSet::to_multipolygon`], and it has a `value` property,
+    /// the result of calling `value` with the shared value.
+    ///
+    /// Values are grouped by comparing each one to those seen so far,
+    /// so this is meant for maps with a handful of distinct values,
+    /// such as region names.
+    ///
+    /// [`HexTreeSet::to_multipolygon`]: crate::HexTreeSet::to_multipolygon
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{geojson::JsonValue, Cell, HexTreeMap};
+    ///
+    /// let parent = Cell::from_raw(0x863969a47ffffff)?;
+    /// let center = parent.center_child(7).unwrap();
+    /// let map: HexTreeMap<&str> = parent
+    ///     .children(7)
+    ///     .map(|cell| (cell, if cell == center { "center" } else { "ring" }))
+    ///     .collect();
+    ///
+    /// let features = map.to_geojson_dissolved(|&v| JsonValue::from(v));
+    /// assert_eq!(features.features.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_geojson_dissolved<F>(&self, mut value: F) -> FeatureCollection
+    where
+        V: PartialEq,
+        F: FnMut(&V) -> JsonValue,
+    {
+        let mut groups: Vec<(&V, Vec<Cell>)> = Vec::new();
+        for (cell, cell_value) in self.iter() {
+            match groups.iter_mut().find(|(group, _)| *group == cell_value) {
+                Some((_, cells)) => cells.push(cell),
+                None => groups.push((cell_value, vec![cell])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(group, cells)| {
+                let res = cells
+                    .iter()
+                    .map(Cell::res)
+                    .max()
+                    .expect("groups aren't empty");
+                let outline = crate::geo::dissolve(
+                    cells,
+                    res,
+                    |cell| matches!(self.get(cell), Some((_, other)) if other == group),
+                );
+                let mut feature = Feature::from(Geometry::new(Value::from(&outline)));
+                feature.set_property("value", value(group));
+                feature
+            })
+            .collect()
+    }
+}
+
+/// Returns the polygons making up `geometry`.
+fn polygons(geometry: &Geometry) -> Result<Vec<geo_types::Polygon<f64>>> {
+    match geo_types::Geometry::try_from(&geometry.value)? {
+        geo_types::Geometry::Polygon(polygon) => Ok(vec![polygon]),
+        geo_types::Geometry::MultiPolygon(multi) => Ok(multi.0),
+        _ => Err(Error::from(geojson::Error::InvalidGeometryConversion {
+            expected_type: "Polygon or MultiPolygon",
+            found_type: geometry.value.type_name(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compaction::{EqCompactor, SetCompactor},
+        HexTreeSet,
+    };
+    use geojson::GeoJson;
+
+    fn monaco() -> HexTreeSet {
+        use byteorder::{LittleEndian as LE, ReadBytesExt};
+        let idx_bytes = include_bytes!("../assets/monaco.res12.h3idx");
+        let rdr = &mut idx_bytes.as_slice();
+        let mut monaco = HexTreeSet::with_compactor(SetCompactor);
+        while let Ok(idx) = rdr.read_u64::<LE>() {
+            monaco.insert(Cell::from_raw(idx).unwrap(), ());
+        }
+        monaco
+    }
+
+    #[test]
+    fn test_geojson_round_trip() {
+        let geometry = match include_str!("../assets/monaco.res12.geojson")
+            .parse::<GeoJson>()
+            .unwrap()
+        {
+            GeoJson::Geometry(geometry) => geometry,
+            other => panic!("expected a geometry, got {:?}", other),
+        };
+        let mut feature = Feature::from(geometry);
+        feature.set_property("name", "Monaco");
+        let features: FeatureCollection = std::iter::once(feature).collect();
+
+        let name = |feature: &Feature| feature.property("name")?.as_str().map(String::from);
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        map.extend_from_geojson(&features, 12, ContainmentMode::ContainsCentroid, name)
+            .unwrap();
+        let monaco = monaco();
+        assert!(!map.is_empty());
+        assert!(map.iter_at_res(12).all(|(cell, _)| monaco.contains(cell)));
+
+        let dissolved = map.to_geojson_dissolved(|name| JsonValue::from(name.as_str()));
+        assert_eq!(dissolved.features.len(), 1);
+        let feature = &dissolved.features[0];
+        assert_eq!(feature.property("value"), Some(&JsonValue::from("Monaco")));
+
+        // Every cell's center is well inside the outline, so reading it
+        // back covers exactly the same cells.
+        let mut read = HexTreeMap::with_compactor(EqCompactor);
+        read.extend_from_geojson(
+            &dissolved,
+            12,
+            ContainmentMode::ContainsCentroid,
+            |feature| feature.property("value")?.as_str().map(String::from),
+        )
+        .unwrap();
+        assert_eq!(
+            read.iter().collect::<Vec<_>>(),
+            map.iter().collect::<Vec<_>>()
+        );
+
+        let per_cell = map.to_geojson_cells(|name| JsonValue::from(name.as_str()));
+        assert_eq!(per_cell.features.len(), map.len());
+        for (feature, (cell, _)) in per_cell.features.iter().zip(map.iter()) {
+            let h3 = feature.property("h3").unwrap().as_str().unwrap();
+            assert_eq!(h3.parse::<Cell>().unwrap(), cell);
+        }
+    }
+
+    #[test]
+    fn test_geojson_dissolved_groups_values() {
+        let parent = Cell::from_raw(0x863969a47ffffff).unwrap();
+        let map: HexTreeMap<u8> = parent
+            .children(8)
+            .enumerate()
+            .map(|(i, cell)| (cell, (i % 3) as u8))
+            .collect();
+        let dissolved = map.to_geojson_dissolved(|&v| JsonValue::from(v));
+        assert_eq!(dissolved.features.len(), 3);
+
+        // Reading each group back recovers exactly its cells.
+        let mut read = HexTreeMap::new();
+        read.extend_from_geojson(
+            &dissolved,
+            8,
+            ContainmentMode::ContainsCentroid,
+            |feature| feature.property("value")?.as_u64().map(|v| v as u8),
+        )
+        .unwrap();
+        assert_eq!(
+            read.iter().collect::<Vec<_>>(),
+            map.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_geojson_later_features_win() {
+        use geo_types::{LineString, Polygon};
+
+        let square = |name: &str, (x0, y0): (f64, f64), (x1, y1): (f64, f64)| {
+            let polygon = Polygon::new(
+                LineString::from(vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]),
+                vec![],
+            );
+            let mut feature = Feature::from(Geometry::new(Value::from(&polygon)));
+            feature.set_property("name", name);
+            feature
+        };
+        let outer = square("outer", (2.0, 48.5), (2.8, 49.1));
+        let inner = square("inner", (2.3, 48.7), (2.5, 48.9));
+        let name = |feature: &Feature| feature.property("name")?.as_str().map(String::from);
+
+        let features: FeatureCollection = vec![outer.clone(), inner.clone()].into_iter().collect();
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        map.extend_from_geojson(&features, 8, ContainmentMode::ContainsCentroid, name)
+            .unwrap();
+        assert!(map
+            .iter()
+            .any(|(cell, name)| name == "outer" && cell.res() < 8));
+        assert_eq!(map.get_point(48.8, 2.4).unwrap().1, "inner");
+        assert_eq!(map.get_point(48.6, 2.1).unwrap().1, "outer");
+
+        let features: FeatureCollection = vec![inner, outer].into_iter().collect();
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        map.extend_from_geojson(&features, 8, ContainmentMode::ContainsCentroid, name)
+            .unwrap();
+        assert!(map.iter().all(|(_, name)| name == "outer"));
+    }
+
+    #[test]
+    fn test_geojson_unsupported_geometry() {
+        let point = Feature::from(Geometry::new(Value::Point(vec![7.42, 43.73])));
+        let features: FeatureCollection = std::iter::once(point).collect();
+        let mut map = HexTreeMap::new();
+        let err = map
+            .extend_from_geojson(&features, 12, ContainmentMode::ContainsCentroid, |_| {
+                Some(())
+            })
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::GeoJson(inner) if matches!(
+                *inner,
+                geojson::Error::InvalidGeometryConversion {
+                    found_type: "Point",
+                    ..
+                }
+            )
+        ));
+        assert!(map.is_empty());
+    }
+}
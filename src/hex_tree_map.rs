@@ -1,14 +1,22 @@
 //! A HexTreeMap is a structure for mapping geographical regions to values.
 
-pub use crate::entry::{Entry, OccupiedEntry, VacantEntry};
 use crate::{
-    cell::CellStack,
-    compaction::{Compactor, NullCompactor},
+    cell::{is_pentagon_base, CellStack},
+    compaction::{ContextCompactor, NullCompactor},
     digits::Digits,
     node::Node,
-    Cell,
+    Cell, Error, Result,
+};
+pub use crate::{
+    entry::{Entry, OccupiedEntry, VacantEntry},
+    iteration::{ExtractIf, IntoIter, Iter, IterMut},
+};
+use std::{
+    cmp::PartialEq,
+    iter::FromIterator,
+    ops::{Bound, RangeBounds},
+    sync::atomic::{AtomicU8, Ordering},
 };
-use std::{cmp::PartialEq, iter::FromIterator};
 
 /// A HexTreeMap is a structure for mapping geographical regions to
 /// values.
@@ -68,6 +76,70 @@ pub struct HexTreeMap<V, C = NullCompactor> {
     pub(crate) nodes: Box<[Option<Box<Node<V>>>]>,
     /// User-provided compactor. Defaults to the null compactor.
     compactor: C,
+    /// The resolution of the finest leaf, once looked up.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    finest: FinestRes,
+}
+
+/// What [`HexTreeMap::insert_with_policy`] does when a coarser leaf
+/// already covers the cell being inserted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertPolicy {
+    /// Leave the map unchanged and hand the value back in
+    /// [`InsertOutcome::Shadowed`]. This is what
+    /// [`HexTreeMap::insert`] always does.
+    KeepExisting,
+    /// Split the covering leaf into its children, each carrying the
+    /// leaf's value, down to the inserted cell's resolution, then
+    /// insert the cell.
+    Split,
+    /// Leave the map unchanged and return [`Error::Shadowed`].
+    ///
+    /// [`Error::Shadowed`]: crate::Error::Shadowed
+    Error,
+}
+
+/// What happened when inserting a cell into a [`HexTreeMap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InsertOutcome<V> {
+    /// The cell was not previously covered by the map, though cells
+    /// below it may have been, which are now replaced.
+    Inserted,
+    /// The cell was covered by the map, and the contained value it
+    /// previously mapped to was replaced.
+    Replaced(V),
+    /// The contained coarser leaf already covers the cell, so the map
+    /// is unchanged and the value is handed back.
+    Shadowed(Cell, V),
+}
+
+/// How far a leaf found by [`HexTreeMap::nearest`] or
+/// [`HexTreeMap::k_nearest`] is from the cell searched from, in rings
+/// of that cell's resolution.
+#[cfg(feature = "geo")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Distance {
+    /// Measured on the grid, as by [`Cell::grid_distance`].
+    Exact(u32),
+    /// Estimated from the great circle distance between cell
+    /// centers, where the grid distance can't be measured: more than
+    /// a base cell away or across a pentagon.
+    Estimated(u32),
+}
+
+#[cfg(feature = "geo")]
+impl Distance {
+    /// Returns the number of rings, whether exact or estimated.
+    pub fn rings(self) -> u32 {
+        match self {
+            Self::Exact(rings) | Self::Estimated(rings) => rings,
+        }
+    }
+
+    /// Returns `true` if the distance was measured on the grid.
+    pub fn is_exact(self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
 }
 
 impl<V> HexTreeMap<V, NullCompactor> {
@@ -82,21 +154,405 @@ impl<V> HexTreeMap<V, NullCompactor> {
                 .take(122)
                 .collect::<Box<[Option<Box<Node<V>>>]>>(),
             compactor: NullCompactor,
+            finest: FinestRes::default(),
         }
     }
 }
 
-impl<V, C: Compactor<V>> HexTreeMap<V, C> {
-    /// Adds a cell/value pair to the set.
-    pub fn insert(&mut self, cell: Cell, value: V) {
+impl<V, C: ContextCompactor<V>> HexTreeMap<V, C> {
+    /// Adds a cell/value pair to the map, returning the value `cell`
+    /// previously mapped to exactly, if any.
+    ///
+    /// Any cells stored below `cell` are replaced along with it; use
+    /// [`insert_replacing`][HexTreeMap::insert_replacing] to get them
+    /// back.
+    ///
+    /// If a coarser leaf already covers `cell`, the map is left
+    /// unchanged, and `value` is dropped and `None` returned just as
+    /// if `cell` had been vacant. Use
+    /// [`insert_with_policy`][HexTreeMap::insert_with_policy] to have
+    /// the shadowed value handed back in [`InsertOutcome::Shadowed`],
+    /// or to split that leaf instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res7 = eiffel_tower_res12.to_parent(7).unwrap();
+    /// let mut map = HexTreeMap::new();
+    ///
+    /// assert_eq!(map.insert(paris_res7, "Paris"), None);
+    /// assert_eq!(map.insert(paris_res7, "Paris, France"), Some("Paris"));
+    /// assert_eq!(map.insert(eiffel_tower_res12, "Eiffel Tower"), None);
+    /// assert_eq!(map[eiffel_tower_res12], "Paris, France");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(&mut self, cell: Cell, value: V) -> Option<V> {
+        match self.insert_node(cell, value) {
+            Ok(Node::Leaf(old)) => Some(old),
+            _ => None,
+        }
+    }
+
+    /// Adds a cell/value pair to the map, returning every cell-value
+    /// pair it displaced, in ascending [`Cell`] order.
+    ///
+    /// This is `cell` itself if it was already in the map, or else
+    /// all of the cells stored below it. As with
+    /// [`insert`][HexTreeMap::insert], the map is left unchanged and
+    /// nothing is returned if a coarser leaf already covers `cell`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let paris_res7 = Cell::from_raw(0x8c1fb46741ae9ff)?.to_parent(7).unwrap();
+    /// let mut map = HexTreeMap::new();
+    /// for cell in paris_res7.children(9) {
+    ///     map.insert(cell, "Paris");
+    /// }
+    ///
+    /// let displaced = map.insert_replacing(paris_res7, "Paris, France");
+    /// assert_eq!(displaced.len(), 49);
+    /// assert!(displaced.iter().all(|(cell, value)| {
+    ///     cell.to_parent(7) == Some(paris_res7) && *value == "Paris"
+    /// }));
+    /// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(paris_res7, &"Paris, France")]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_replacing(&mut self, cell: Cell, value: V) -> Vec<(Cell, V)> {
+        match self.insert_node(cell, value) {
+            Ok(Node::Leaf(old)) => vec![(cell, old)],
+            Ok(Node::Parent(children)) => IntoIter::children(children, cell).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Inserts `value` for `cell`, returning the node it displaced,
+    /// or the covering leaf's cell and `value` if it was shadowed.
+    fn insert_node(&mut self, cell: Cell, value: V) -> std::result::Result<Node<V>, (Cell, V)> {
+        self.finest.forget();
         let base_cell = cell.base();
         let digits = Digits::new(cell);
         match self.nodes[base_cell as usize].as_mut() {
             Some(node) => node.insert(cell, 0_u8, digits, value, &mut self.compactor),
             None => {
                 let mut node = Box::new(Node::new());
-                node.insert(cell, 0_u8, digits, value, &mut self.compactor);
+                let displaced = node.insert(cell, 0_u8, digits, value, &mut self.compactor);
                 self.nodes[base_cell as usize] = Some(node);
+                displaced
+            }
+        }
+    }
+
+    /// Runs this map's compactor over every node in the tree.
+    ///
+    /// Compaction normally happens during `insert`, but only along
+    /// the inserted cell's path. This method instead walks the entire
+    /// tree bottom-up, so it's useful after bulk-loading a map with
+    /// [`NullCompactor`] and later switching compactors with
+    /// [`replace_compactor`][HexTreeMap::replace_compactor].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{compaction::EqCompactor, Cell, HexTreeMap};
+    ///
+    /// let children: HexTreeMap<&str> = (0x835990fffffffff..=0x835996fffffffff)
+    ///     .step_by(0x1000000000)
+    ///     .map(|idx| Cell::from_raw(idx).map(|cell| (cell, "US915")))
+    ///     .collect::<hextree::Result<_>>()?;
+    /// assert_eq!(children.len(), 7);
+    ///
+    /// let mut compacted = children.replace_compactor(EqCompactor);
+    /// compacted.compact();
+    /// assert_eq!(compacted.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compact(&mut self) {
+        self.finest.forget();
+        compact_nodes(&mut self.nodes, &mut self.compactor)
+    }
+
+    /// Retains only the cells for which `f` returns `true`.
+    ///
+    /// The tree is walked in place in ascending [`Cell`] order,
+    /// removing rejected leaves and pruning parents left without any
+    /// children. Since `f` may modify the values it keeps, every
+    /// surviving parent is then re-run through this map's compactor,
+    /// so siblings which now compact are coalesced. Use
+    /// [`NullCompactor`] to skip this step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{compaction::EqCompactor, Cell, HexTreeMap};
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// let mut map = HexTreeMap::with_compactor(EqCompactor);
+    /// for (i, cell) in parent.children(3).enumerate() {
+    ///     map.insert(cell, i);
+    /// }
+    /// assert_eq!(map.len(), 7);
+    ///
+    /// // Keep everything, but round values down to a multiple of 10.
+    /// map.retain(|_cell, value| {
+    ///     *value -= *value % 10;
+    ///     true
+    /// });
+    /// // With every value now equal, the children compact.
+    /// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(parent, &0)]);
+    ///
+    /// map.retain(|_cell, value| *value > 0);
+    /// assert!(map.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Cell, &mut V) -> bool,
+    {
+        self.finest.forget();
+        let mut cell_stack = CellStack::new();
+        for (base, node) in self.nodes.iter_mut().enumerate() {
+            cell_stack.push(base as u8);
+            Node::retain(node, &mut cell_stack, &mut self.compactor, &mut f);
+            cell_stack.pop();
+        }
+    }
+}
+
+impl<V: Clone, C> HexTreeMap<V, C> {
+    /// Removes `cell` from the map, returning the value of the leaf
+    /// which covered it.
+    ///
+    /// If `cell` lies under a coarser, possibly compacted, leaf, that
+    /// leaf is split back into its children down to `cell`'s
+    /// resolution, and all of `cell`'s siblings along the way keep
+    /// the original value. Parents left empty by the removal are
+    /// pruned.
+    ///
+    /// If `cell` has no value of its own but finer cells are stored
+    /// below it, nothing is removed and `None` is returned. Use
+    /// [`drain_descendants`][HexTreeMap::drain_descendants] to remove
+    /// those.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let mut set: HexTreeSet = [
+    ///     0x835990fffffffff,
+    ///     0x835991fffffffff,
+    ///     0x835992fffffffff,
+    ///     0x835993fffffffff,
+    ///     0x835994fffffffff,
+    ///     0x835995fffffffff,
+    ///     0x835996fffffffff,
+    /// ]
+    /// .iter()
+    /// .map(|&idx| Cell::from_raw(idx))
+    /// .collect::<hextree::Result<_>>()?;
+    /// // All 7 children were compacted into their parent.
+    /// assert_eq!(set.len(), 1);
+    ///
+    /// let removed = Cell::from_raw(0x835990fffffffff)?;
+    /// assert_eq!(set.remove(removed), Some(()));
+    /// assert!(!set.contains(removed));
+    /// assert_eq!(set.len(), 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, cell: Cell) -> Option<V> {
+        self.finest.forget();
+        let base_cell = cell.base();
+        let digits = Digits::new(cell);
+        Node::remove(
+            &mut self.nodes[base_cell as usize],
+            digits,
+            is_pentagon_base(base_cell),
+        )
+    }
+
+    /// Removes `cell` and everything stored below it from the map,
+    /// returning the removed cell-value pairs as an iterator in
+    /// ascending [`Cell`] order.
+    ///
+    /// If `cell` lies under a coarser leaf, that leaf is split as
+    /// with [`remove`][HexTreeMap::remove] and `cell` itself is
+    /// yielded with the leaf's value. The subtree is removed
+    /// immediately, even if the iterator is dropped before being fully
+    /// consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// let mut map: HexTreeMap<u64> = parent
+    ///     .children(4)
+    ///     .map(|cell| (cell, cell.into_raw()))
+    ///     .collect();
+    /// assert_eq!(map.len(), 49);
+    ///
+    /// let child = Cell::from_raw(0x835990fffffffff)?;
+    /// let drained: Vec<(Cell, u64)> = map.drain_descendants(child).collect();
+    /// assert_eq!(drained.len(), 7);
+    /// assert!(drained.iter().all(|(cell, value)| cell.into_raw() == *value));
+    /// assert_eq!(map.len(), 42);
+    /// assert_eq!(map.descendants(child).count(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn drain_descendants(&mut self, cell: Cell) -> impl Iterator<Item = (Cell, V)> {
+        self.finest.forget();
+        let base_cell = cell.base();
+        let digits = Digits::new(cell);
+        let taken = Node::take(
+            &mut self.nodes[base_cell as usize],
+            digits,
+            is_pentagon_base(base_cell),
+        );
+        match taken.map(|node| *node) {
+            Some(Node::Leaf(value)) => Some((cell, value)).into_iter().chain(IntoIter::empty()),
+            Some(Node::Parent(children)) => {
+                None.into_iter().chain(IntoIter::children(children, cell))
+            }
+            None => None.into_iter().chain(IntoIter::empty()),
+        }
+    }
+}
+
+impl<V: Clone, C: ContextCompactor<V>> HexTreeMap<V, C> {
+    /// Adds a cell/value pair to the map, using `policy` to decide
+    /// what to do if a coarser leaf already covers `cell`.
+    ///
+    /// When `policy` is [`InsertPolicy::Split`] and a leaf is split,
+    /// the value `cell` previously inherited from it is returned in
+    /// [`InsertOutcome::Replaced`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Shadowed`] if `policy` is
+    /// [`InsertPolicy::Error`] and a coarser leaf covers `cell`.
+    ///
+    /// [`Error::Shadowed`]: crate::Error::Shadowed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{
+    ///     hex_tree_map::{InsertOutcome, InsertPolicy},
+    ///     Cell, HexTreeMap,
+    /// };
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res7 = eiffel_tower_res12.to_parent(7).unwrap();
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(paris_res7, "Paris");
+    ///
+    /// assert!(map
+    ///     .insert_with_policy(eiffel_tower_res12, "Eiffel Tower", InsertPolicy::Error)
+    ///     .is_err());
+    /// assert_eq!(
+    ///     map.insert_with_policy(eiffel_tower_res12, "Eiffel Tower", InsertPolicy::Split)?,
+    ///     InsertOutcome::Replaced("Paris")
+    /// );
+    /// assert_eq!(map[eiffel_tower_res12], "Eiffel Tower");
+    /// // Every resolution between 8 and 12 holds the 6 siblings of the
+    /// // Eiffel Tower's ancestor, which keep the original value.
+    /// assert_eq!(map.len(), 6 * 5 + 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_with_policy(
+        &mut self,
+        cell: Cell,
+        value: V,
+        policy: InsertPolicy,
+    ) -> Result<InsertOutcome<V>> {
+        match (self.covering_leaf(cell), policy) {
+            (Some(ancestor), InsertPolicy::Split) if ancestor != cell => {
+                let inherited = self.remove(cell).expect("ancestor covers cell");
+                self.insert(cell, value);
+                Ok(InsertOutcome::Replaced(inherited))
+            }
+            (Some(ancestor), InsertPolicy::Error) if ancestor != cell => {
+                Err(Error::Shadowed(cell, ancestor))
+            }
+            _ => Ok(match self.insert_node(cell, value) {
+                Ok(Node::Leaf(old)) => InsertOutcome::Replaced(old),
+                Ok(Node::Parent(_)) => InsertOutcome::Inserted,
+                Err((ancestor, value)) => InsertOutcome::Shadowed(ancestor, value),
+            }),
+        }
+    }
+
+    /// Merges every cell/value pair of `other` into `self`.
+    ///
+    /// Cells covered by only one of the two maps keep their value.
+    /// Where both maps cover the same cell, `f` is called with that
+    /// cell, `self`'s value, and `other`'s value, and its return value
+    /// is stored. If one map holds a coarse leaf over a region where
+    /// the other holds finer cells, the coarse value is first pushed
+    /// down to the finer cells' resolution, so `f` is called once per
+    /// overlapping fine cell.
+    ///
+    /// `self`'s compactor is applied along every merged path, so equal
+    /// siblings may coalesce back into their parent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{compaction::EqCompactor, Cell, HexTreeMap};
+    ///
+    /// let eiffel_tower_res9 = Cell::from_raw(0x891fb46741bffff)?;
+    /// let paris_res4 = eiffel_tower_res9.to_parent(4).unwrap();
+    ///
+    /// let mut carrier_a = HexTreeMap::with_compactor(EqCompactor);
+    /// carrier_a.insert(paris_res4, 1);
+    /// let mut carrier_b = HexTreeMap::new();
+    /// carrier_b.insert(eiffel_tower_res9, 2);
+    ///
+    /// carrier_a.merge(&carrier_b, |_cell, a, b| a + b);
+    /// assert_eq!(carrier_a.get(eiffel_tower_res9), Some((eiffel_tower_res9, &3)));
+    /// assert_eq!(carrier_a.get(paris_res4), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge<OtherC, F>(&mut self, other: &HexTreeMap<V, OtherC>, mut f: F)
+    where
+        F: FnMut(Cell, &V, &V) -> V,
+    {
+        self.finest.forget();
+        let mut cell_stack = CellStack::new();
+        for (base, other) in other.nodes.iter().enumerate() {
+            if let Some(other) = other.as_deref() {
+                cell_stack.push(base as u8);
+                Node::merge(
+                    &mut self.nodes[base],
+                    other,
+                    &mut cell_stack,
+                    &mut f,
+                    &mut self.compactor,
+                );
+                cell_stack.pop();
             }
         }
     }
@@ -114,6 +570,7 @@ impl<V, C> HexTreeMap<V, C> {
                 .take(122)
                 .collect::<Box<[Option<Box<Node<V>>>]>>(),
             compactor,
+            finest: FinestRes::default(),
         }
     }
 
@@ -127,9 +584,23 @@ impl<V, C> HexTreeMap<V, C> {
         HexTreeMap {
             nodes: self.nodes,
             compactor: new_compactor,
+            finest: self.finest,
         }
     }
 
+    /// Compacts the entire tree with `compactor`, a one-off compactor
+    /// which is dropped afterwards.
+    ///
+    /// This behaves like [`compact`][HexTreeMap::compact], but leaves
+    /// this map's own compactor in place for future inserts.
+    pub fn compact_with<OtherC>(&mut self, mut compactor: OtherC)
+    where
+        OtherC: ContextCompactor<V>,
+    {
+        self.finest.forget();
+        compact_nodes(&mut self.nodes, &mut compactor)
+    }
+
     /// Returns the number of H3 cells in the map.
     ///
     /// This method only counts leaf cells (complete entries) in the
@@ -164,6 +635,89 @@ impl<V, C> HexTreeMap<V, C> {
         }
     }
 
+    /// Returns `true` if every cell covered by `self` is also covered
+    /// by `other`.
+    ///
+    /// Only keys are compared; values are ignored. A leaf in `other`
+    /// covers every cell below it, so a map built from fine cells is a
+    /// subset of any map containing one of their parents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+    /// let licensed: HexTreeSet = std::iter::once(paris_res3).collect();
+    /// let mut region = HexTreeMap::new();
+    /// region.insert(eiffel_tower_res12, "Eiffel Tower");
+    ///
+    /// assert!(region.is_subset(&licensed));
+    /// assert!(!licensed.is_subset(&region));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_subset<U, OtherC>(&self, other: &HexTreeMap<U, OtherC>) -> bool {
+        self.nodes
+            .iter()
+            .zip(other.nodes.iter())
+            .enumerate()
+            .all(|(base, pair)| match pair {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(node), Some(other)) => node.is_subset(other, is_pentagon_base(base as u8)),
+            })
+    }
+
+    /// Returns `true` if every cell covered by `other` is also covered
+    /// by `self`.
+    ///
+    /// Only keys are compared; values are ignored.
+    pub fn is_superset<U, OtherC>(&self, other: &HexTreeMap<U, OtherC>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if `self` and `other` cover at least one cell in
+    /// common.
+    ///
+    /// Only keys are compared; values are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let paris: HexTreeSet = std::iter::once(eiffel_tower_res12.to_parent(3).unwrap()).collect();
+    /// let exclusion_zone: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+    /// let monaco: HexTreeSet = std::iter::once(monaco_res12).collect();
+    ///
+    /// assert!(paris.intersects(&exclusion_zone));
+    /// assert!(paris.is_disjoint(&monaco));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intersects<U, OtherC>(&self, other: &HexTreeMap<U, OtherC>) -> bool {
+        self.nodes
+            .iter()
+            .zip(other.nodes.iter())
+            .any(|pair| match pair {
+                (Some(node), Some(other)) => node.intersects(other),
+                _ => false,
+            })
+    }
+
+    /// Returns `true` if `self` and `other` have no cells in common.
+    ///
+    /// Only keys are compared; values are ignored.
+    pub fn is_disjoint<U, OtherC>(&self, other: &HexTreeMap<U, OtherC>) -> bool {
+        !self.intersects(other)
+    }
+
     /// Returns a reference to the value for the given cell or its nearest parent.
     ///
     /// Returns `Some((cell, value))` where `cell` is either the queried cell
@@ -177,6 +731,197 @@ impl<V, C> HexTreeMap<V, C> {
         }
     }
 
+    /// Returns a reference to the value stored for exactly `cell`.
+    ///
+    /// Unlike [`get`][HexTreeMap::get], this returns `None` if `cell`
+    /// is only covered by a coarser, possibly compacted, parent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res7 = eiffel_tower_res12.to_parent(7).unwrap();
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(paris_res7, "Paris");
+    ///
+    /// assert_eq!(map.get_exact(paris_res7), Some(&"Paris"));
+    /// assert_eq!(map.get_exact(eiffel_tower_res12), None);
+    /// assert_eq!(map.get(eiffel_tower_res12), Some((paris_res7, &"Paris")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn get_exact(&self, cell: Cell) -> Option<&V> {
+        match self.get(cell) {
+            Some((leaf, val)) if leaf == cell => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the leaf cell responsible for `cell`, which is either
+    /// `cell` itself or the coarser parent containing it.
+    ///
+    /// Returns `None` if `cell` is not covered by any leaf, including
+    /// when only some of its descendants are in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res7 = eiffel_tower_res12.to_parent(7).unwrap();
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(paris_res7, "Paris");
+    ///
+    /// assert_eq!(map.covering_leaf(eiffel_tower_res12), Some(paris_res7));
+    /// assert_eq!(map.covering_leaf(paris_res7), Some(paris_res7));
+    /// assert_eq!(map.covering_leaf(paris_res7.to_parent(6).unwrap()), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn covering_leaf(&self, cell: Cell) -> Option<Cell> {
+        self.get(cell).map(|(leaf, _)| leaf)
+    }
+
+    /// Returns a reference to the value for the cell containing the
+    /// point at `lat`, `lng` degrees, or its nearest parent.
+    ///
+    /// The point is converted to a cell at the resolution of this
+    /// map's finest leaf and looked up as by [`get`]. So a point is
+    /// found by the leaf, or compacted parent, covering the cell
+    /// [`Cell::from_latlng`] gives at the resolution the map was built
+    /// at, even near a leaf's edge where coarser and finer cells
+    /// don't nest.
+    ///
+    /// Finding that resolution visits every node, so the first lookup
+    /// after the map is modified takes time proportional to its size.
+    /// The resolution is then remembered until the map is next
+    /// modified, so later lookups only descend the tree once.
+    ///
+    /// Returns `None` if the point is not covered or either coordinate
+    /// is not finite.
+    ///
+    /// [`get`]: HexTreeMap::get
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(Cell::from_raw(0x863969a47ffffff)?, "Monaco");
+    ///
+    /// let (cell, value) = map.get_point(43.73631, 7.42418).unwrap();
+    /// assert_eq!(cell, Cell::from_raw(0x863969a47ffffff)?);
+    /// assert_eq!(*value, "Monaco");
+    /// assert_eq!(map.get_point(43.73631, 8.0), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn get_point(&self, lat: f64, lng: f64) -> Option<(Cell, &V)> {
+        let res = self.finest.get_or_init(|| self.finest_res())?;
+        let cell = Cell::from_latlng(lat, lng, res).ok()?;
+        self.get(cell)
+    }
+
+    /// Returns the leaf nearest to `cell`, its value, and how many
+    /// rings of `cell`'s resolution out from `cell` it is.
+    ///
+    /// A leaf containing `cell` is 0 rings away. The [`Distance`] is
+    /// [`Estimated`][Distance::Estimated] if the leaf is too far away
+    /// to measure on the grid. Returns `None` only if the map is
+    /// empty.
+    ///
+    /// See [`k_nearest`][Self::k_nearest] for how the search works.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{hex_tree_map::Distance, Cell, HexTreeMap};
+    ///
+    /// let device = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let tower = device.grid_ring(3)[0];
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(tower, "tower");
+    ///
+    /// assert_eq!(map.nearest(device), Some((tower, &"tower", Distance::Exact(3))));
+    /// assert_eq!(map.nearest(tower), Some((tower, &"tower", Distance::Exact(0))));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn nearest(&self, cell: Cell) -> Option<(Cell, &V, Distance)> {
+        self.k_nearest(cell, 1).pop()
+    }
+
+    /// Returns up to `k` leaves nearest to `cell`, with their values
+    /// and how many rings of `cell`'s resolution out from `cell` they
+    /// are, nearest first.
+    ///
+    /// A leaf finer than `cell`'s resolution is as far as its ancestor
+    /// at that resolution, and a coarser one as far as the nearest of
+    /// its descendants at that resolution. Leaves at the same distance
+    /// come in ascending [`Cell`] order.
+    ///
+    /// Distances are measured as by [`Cell::grid_distance`] where
+    /// possible, and returned as [`Distance::Exact`]. For leaves it
+    /// can't measure, more than a base cell away or across a pentagon,
+    /// the distance is estimated from the great circle distance
+    /// between cell centers, in steps the size of `cell`'s distance to
+    /// its neighbors, and returned as [`Distance::Estimated`]. So a
+    /// cell far outside the map's coverage still finds its nearest
+    /// leaves, though their distances, and so their order, are
+    /// approximate.
+    ///
+    /// The search walks down the tree from its non-empty base cells,
+    /// nearest first, only descending into a subtree while its
+    /// leaves could still be among the `k` nearest. So its cost
+    /// depends on the number of leaves nearby rather than how far
+    /// away they are. Near pentagons, where distances can't be
+    /// bounded in advance, it may visit more of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let device = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(device.grid_ring(5)[0], "far");
+    /// map.insert(device.grid_ring(2)[0], "near");
+    /// map.insert(device.grid_ring(7)[0], "farther");
+    ///
+    /// let nearest: Vec<_> = map
+    ///     .k_nearest(device, 2)
+    ///     .into_iter()
+    ///     .map(|(_, value, distance)| (*value, distance.rings()))
+    ///     .collect();
+    /// assert_eq!(nearest, vec![("near", 2), ("far", 5)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn k_nearest(&self, cell: Cell, k: usize) -> Vec<(Cell, &V, Distance)> {
+        crate::geo::k_nearest(&self.nodes, cell, k)
+    }
+
+    /// Returns the resolution of this tree's finest leaf, or `None` if
+    /// it's empty.
+    #[cfg(feature = "geo")]
+    pub(crate) fn finest_res(&self) -> Option<u8> {
+        self.nodes.iter().flatten().map(|node| node.depth()).max()
+    }
+
     #[inline]
     pub(crate) fn get_raw(&self, cell: Cell) -> Option<(Cell, &Node<V>)> {
         let base_cell = cell.base();
@@ -228,15 +973,203 @@ impl<V, C> HexTreeMap<V, C> {
         })
     }
 
-    /// An iterator visiting all cell-value pairs in arbitrary order.
-    pub fn iter(&self) -> impl Iterator<Item = (Cell, &V)> {
-        crate::iteration::Iter::new(&self.nodes, CellStack::new())
+    /// An iterator visiting all cell-value pairs in ascending [`Cell`]
+    /// order.
+    ///
+    /// This is a depth-first walk of the tree, base cells first, then
+    /// digits 0 through 6 at each resolution, which is the same order
+    /// as [`Cell`]'s [`Ord`] implementation. The order is stable across
+    /// calls and independent of insertion order, so it can be relied
+    /// on to, e.g., merge two maps or resume a scan with
+    /// [`range`][HexTreeMap::range].
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.nodes, CellStack::new())
+    }
+
+    /// An iterator visiting the cell-value pairs whose cells fall in
+    /// `range`, in ascending [`Cell`] order.
+    ///
+    /// Like [`BTreeMap::range`][std::collections::BTreeMap::range],
+    /// this seeks directly to the start of the range instead of
+    /// walking every preceding cell, which makes it suitable for
+    /// paging through large maps with a resumable cursor.
+    ///
+    /// Keep in mind that cells sort after all of their descendants, so
+    /// a range starting at a cell skips the map's leaves inside that
+    /// cell but includes a leaf containing it. Use
+    /// [`descendants`][HexTreeMap::descendants] to scan a region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    /// use std::ops::Bound;
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// let map: HexTreeMap<u8> = parent
+    ///     .children(3)
+    ///     .enumerate()
+    ///     .map(|(i, cell)| (cell, i as u8))
+    ///     .collect();
+    ///
+    /// // Page through the map two cells at a time.
+    /// let mut cursor = Bound::Unbounded;
+    /// let mut pages = Vec::new();
+    /// loop {
+    ///     let page: Vec<u8> = map
+    ///         .range((cursor, Bound::Unbounded))
+    ///         .take(2)
+    ///         .map(|(cell, value)| {
+    ///             cursor = Bound::Excluded(cell);
+    ///             *value
+    ///         })
+    ///         .collect();
+    ///     if page.is_empty() {
+    ///         break;
+    ///     }
+    ///     pages.push(page);
+    /// }
+    /// assert_eq!(pages, vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6]]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range<R: RangeBounds<Cell>>(&self, range: R) -> impl Iterator<Item = (Cell, &V)> {
+        let iter = match range.start_bound() {
+            Bound::Included(start) => crate::iteration::Iter::seek(&self.nodes, *start, true),
+            Bound::Excluded(start) => crate::iteration::Iter::seek(&self.nodes, *start, false),
+            Bound::Unbounded => crate::iteration::Iter::new(&self.nodes, CellStack::new()),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Bound::Included(*end),
+            Bound::Excluded(end) => Bound::Excluded(*end),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        iter.take_while(move |(cell, _)| match end {
+            Bound::Included(end) => *cell <= end,
+            Bound::Excluded(end) => *cell < end,
+            Bound::Unbounded => true,
+        })
+    }
+
+    /// An iterator visiting every cell at resolution `res` covered by
+    /// this map, in the same order as [`iter`][HexTreeMap::iter].
+    ///
+    /// Leaves coarser than `res` are lazily expanded into all of their
+    /// children at `res`, each yielding the leaf's value. Leaves finer
+    /// than `res` are truncated to their parent at `res`, which is
+    /// yielded only once with the value of the first such leaf.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `res` is greater than 15.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(eiffel_tower_res12.to_parent(7).unwrap(), "Paris");
+    ///
+    /// assert_eq!(map.iter_at_res(9).count(), 49);
+    /// assert!(map.iter_at_res(9).all(|(cell, _)| cell.res() == 9));
+    /// assert_eq!(
+    ///     map.iter_at_res(5).collect::<Vec<_>>(),
+    ///     vec![(eiffel_tower_res12.to_parent(5).unwrap(), &"Paris")]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_at_res(&self, res: u8) -> impl Iterator<Item = (Cell, &V)> {
+        crate::iteration::IterAtRes::new(
+            crate::iteration::Iter::new(&self.nodes, CellStack::new()),
+            res,
+        )
     }
 
-    /// An iterator visiting all cell-value pairs in arbitrary order
-    /// with mutable references to the values.
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Cell, &mut V)> {
-        crate::iteration::IterMut::new(&mut self.nodes, CellStack::new())
+    /// An iterator visiting all cell-value pairs in ascending [`Cell`]
+    /// order with mutable references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut::new(&mut self.nodes, CellStack::new())
+    }
+
+    /// Removes every cell-value pair from the map, returning them as
+    /// an iterator in ascending [`Cell`] order.
+    ///
+    /// The map is emptied immediately, even if the iterator is
+    /// dropped before being fully consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let cell = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let mut map = HexTreeMap::new();
+    /// map.insert(cell, String::from("Eiffel Tower"));
+    ///
+    /// let drained: Vec<(Cell, String)> = map.drain().collect();
+    /// assert_eq!(drained, vec![(cell, String::from("Eiffel Tower"))]);
+    /// assert!(map.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn drain(&mut self) -> IntoIter<V> {
+        self.finest.forget();
+        let nodes = std::mem::replace(
+            &mut self.nodes,
+            std::iter::repeat_with(|| None)
+                .take(122)
+                .collect::<Box<[Option<Box<Node<V>>>]>>(),
+        );
+        IntoIter::new(nodes)
+    }
+
+    /// Returns an iterator which removes and yields the cells for
+    /// which `pred` returns `true`, in ascending [`Cell`] order.
+    ///
+    /// Cells for which `pred` returns `false` are kept, including
+    /// any changes `pred` made to their values. Each step seeks past
+    /// the last visited cell, so only the cells actually visited are
+    /// tested; if the iterator is dropped early, the rest of the map
+    /// is left untouched. Parents left without any children are
+    /// pruned, but unlike [`retain`][HexTreeMap::retain] the
+    /// survivors are not re-compacted; call
+    /// [`compact`][HexTreeMap::compact] afterwards if `pred` modifies
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeMap};
+    ///
+    /// let parent = Cell::from_raw(0x825997fffffffff)?;
+    /// let mut map: HexTreeMap<usize> = parent
+    ///     .children(3)
+    ///     .enumerate()
+    ///     .map(|(i, cell)| (cell, i))
+    ///     .collect();
+    ///
+    /// let odd: Vec<usize> = map
+    ///     .extract_if(|_cell, value| *value % 2 == 1)
+    ///     .map(|(_cell, value)| value)
+    ///     .collect();
+    /// assert_eq!(odd, vec![1, 3, 5]);
+    /// assert_eq!(map.len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, V, C, F>
+    where
+        F: FnMut(Cell, &mut V) -> bool,
+    {
+        self.finest.forget();
+        ExtractIf::new(self, pred)
     }
 
     /// An iterator visiting the specified cell or its children with
@@ -282,6 +1215,21 @@ impl<V, C> HexTreeMap<V, C> {
     }
 }
 
+/// Compacts every base cell's subtree with `compactor`.
+fn compact_nodes<V, C>(nodes: &mut [Option<Box<Node<V>>>], compactor: &mut C)
+where
+    C: ContextCompactor<V>,
+{
+    let mut cell_stack = CellStack::new();
+    for (base, node) in nodes.iter_mut().enumerate() {
+        if let Some(node) = node {
+            cell_stack.push(base as u8);
+            node.compact(&mut cell_stack, compactor);
+            cell_stack.pop();
+        }
+    }
+}
+
 impl<V: PartialEq> Default for HexTreeMap<V, NullCompactor> {
     fn default() -> Self {
         HexTreeMap::new()
@@ -314,22 +1262,49 @@ impl<'a, V: Copy + 'a> FromIterator<(&'a Cell, &'a V)> for HexTreeMap<V, NullCom
     }
 }
 
-impl<V, C: Compactor<V>> Extend<(Cell, V)> for HexTreeMap<V, C> {
+impl<V, C: ContextCompactor<V>> Extend<(Cell, V)> for HexTreeMap<V, C> {
     fn extend<I: IntoIterator<Item = (Cell, V)>>(&mut self, iter: I) {
         for (cell, val) in iter {
-            self.insert(cell, val)
+            self.insert(cell, val);
         }
     }
 }
 
-impl<'a, V: Copy + 'a, C: Compactor<V>> Extend<(&'a Cell, &'a V)> for HexTreeMap<V, C> {
+impl<'a, V: Copy + 'a, C: ContextCompactor<V>> Extend<(&'a Cell, &'a V)> for HexTreeMap<V, C> {
     fn extend<I: IntoIterator<Item = (&'a Cell, &'a V)>>(&mut self, iter: I) {
         for (cell, val) in iter {
-            self.insert(*cell, *val)
+            self.insert(*cell, *val);
         }
     }
 }
 
+impl<V, C> IntoIterator for HexTreeMap<V, C> {
+    type Item = (Cell, V);
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(self) -> IntoIter<V> {
+        IntoIter::new(self.nodes)
+    }
+}
+
+impl<'a, V, C> IntoIterator for &'a HexTreeMap<V, C> {
+    type Item = (Cell, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<'a, V, C> IntoIterator for &'a mut HexTreeMap<V, C> {
+    type Item = (Cell, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    fn into_iter(self) -> IterMut<'a, V> {
+        self.iter_mut()
+    }
+}
+
 impl<V, C> std::ops::Index<Cell> for HexTreeMap<V, C> {
     type Output = V;
 
@@ -448,6 +1423,50 @@ impl<V, C> std::ops::IndexMut<&Cell> for HexTreeMap<V, C> {
     }
 }
 
+/// The resolution of a map's finest leaf, found on first use by
+/// [`HexTreeMap::get_point`] and forgotten whenever the map is
+/// modified.
+///
+/// As it's derived from the tree, it takes no part in comparing or
+/// serializing maps.
+#[derive(Default)]
+struct FinestRes(AtomicU8);
+
+impl FinestRes {
+    /// Returns the remembered resolution, or remembers the one `init`
+    /// returns. A stored 0 means none is remembered, so the
+    /// resolution is stored plus one.
+    #[cfg(feature = "geo")]
+    fn get_or_init(&self, init: impl FnOnce() -> Option<u8>) -> Option<u8> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => {
+                let res = init()?;
+                self.0.store(res + 1, Ordering::Relaxed);
+                Some(res)
+            }
+            stored => Some(stored - 1),
+        }
+    }
+
+    fn forget(&mut self) {
+        *self.0.get_mut() = 0;
+    }
+}
+
+impl Clone for FinestRes {
+    fn clone(&self) -> Self {
+        Self(AtomicU8::new(self.0.load(Ordering::Relaxed)))
+    }
+}
+
+impl PartialEq for FinestRes {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for FinestRes {}
+
 impl<V: std::fmt::Debug, C> std::fmt::Debug for HexTreeMap<V, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("{")?;
@@ -465,6 +1484,7 @@ impl<V: std::fmt::Debug, C> std::fmt::Debug for HexTreeMap<V, C> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::rc::Rc;
 
     #[test]
     fn map_is_send() {
@@ -477,4 +1497,620 @@ mod tests {
         fn assert_sync<T: Sync>() {}
         assert_sync::<HexTreeMap<i32>>();
     }
+
+    #[test]
+    fn test_compact() {
+        use crate::{compaction::SetCompactor, HexTreeSet};
+        use h3_lorawan_regions::nocompact::US915 as US915_INDICES;
+
+        let cells: Vec<Cell> = US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect();
+        let expected: HexTreeSet = cells.iter().collect();
+        let uncompacted: HexTreeMap<()> = cells.iter().map(|&cell| (cell, ())).collect();
+        assert_eq!(uncompacted.len(), cells.len());
+        assert!(expected.len() < cells.len());
+
+        let mut compacted = uncompacted.clone();
+        compacted.compact_with(SetCompactor);
+        assert_eq!(compacted.replace_compactor(SetCompactor), expected);
+
+        let mut compacted = uncompacted.replace_compactor(SetCompactor);
+        compacted.compact();
+        assert_eq!(compacted, expected);
+    }
+
+    #[test]
+    fn test_remove_exact() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let mut map = HexTreeMap::new();
+        map.insert(eiffel_tower_res12, "Paris");
+        assert_eq!(map.remove(eiffel_tower_res12), Some("Paris"));
+        assert_eq!(map.remove(eiffel_tower_res12), None);
+        assert!(map.is_empty());
+        // Empty parents must be pruned all the way up to the base
+        // cell.
+        assert!(map.nodes.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_remove_splits_compacted_parent() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let eiffel_tower_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+        let mut set: crate::HexTreeSet = std::iter::once(eiffel_tower_res3).collect();
+
+        assert_eq!(set.remove(eiffel_tower_res12), Some(()));
+        assert!(!set.contains(eiffel_tower_res12));
+        assert!(!set.contains(eiffel_tower_res3));
+        // Every resolution between 4 and 12 leaves behind the 6
+        // siblings of the removed cell's ancestor.
+        assert_eq!(set.len(), 6 * 9);
+        for (cell, _) in set.iter() {
+            assert!(!cell.is_related_to(&eiffel_tower_res12));
+            assert_eq!(cell.to_parent(3), Some(eiffel_tower_res3));
+            let parent = cell.to_parent(cell.res() - 1).unwrap();
+            assert!(parent.is_related_to(&eiffel_tower_res12));
+        }
+    }
+
+    #[test]
+    fn test_get_exact_and_covering_leaf() {
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let child = Cell::from_raw(0x835990fffffffff).unwrap();
+        let grandchild = child.center_child(4).unwrap();
+
+        // The children are compacted into their parent.
+        let set: crate::HexTreeSet = parent.children(3).collect();
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get_exact(parent), Some(&()));
+        assert_eq!(set.get_exact(child), None);
+        assert_eq!(set.covering_leaf(child), Some(parent));
+        assert_eq!(set.covering_leaf(grandchild), Some(parent));
+
+        // Without compaction, the children stay exact matches but
+        // their parent is no longer covered by a single leaf.
+        let map: HexTreeMap<()> = parent.children(3).map(|cell| (cell, ())).collect();
+        assert_eq!(map.get_exact(child), Some(&()));
+        assert_eq!(map.get_exact(parent), None);
+        assert_eq!(map.covering_leaf(parent), None);
+        assert_eq!(map.covering_leaf(grandchild), Some(child));
+    }
+
+    #[test]
+    fn test_insert_with_policy() {
+        use crate::compaction::EqCompactor;
+
+        // Base cell 4 is a pentagon, so every split along its center
+        // children leaves 5 siblings instead of 6.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let center = pentagon.center_child(3).unwrap();
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        assert_eq!(map.insert(pentagon, 1), None);
+
+        let mut keep = map.clone();
+        assert_eq!(
+            keep.insert_with_policy(center, 2, InsertPolicy::KeepExisting)
+                .unwrap(),
+            InsertOutcome::Shadowed(pentagon, 2)
+        );
+        assert_eq!(keep, map);
+        assert!(matches!(
+            map.insert_with_policy(center, 2, InsertPolicy::Error),
+            Err(Error::Shadowed(cell, ancestor)) if cell == center && ancestor == pentagon
+        ));
+
+        let mut split = map.clone();
+        assert_eq!(
+            split
+                .insert_with_policy(center, 2, InsertPolicy::Split)
+                .unwrap(),
+            InsertOutcome::Replaced(1)
+        );
+        assert_eq!(split.len(), 5 * 3 + 1);
+        assert_eq!(split.get(center), Some((center, &2)));
+        assert!(split
+            .iter()
+            .filter(|(cell, _)| *cell != center)
+            .all(|(_, value)| *value == 1));
+        // Splitting with the leaf's own value compacts right back.
+        let mut same = map.clone();
+        assert_eq!(
+            same.insert_with_policy(center, 1, InsertPolicy::Split)
+                .unwrap(),
+            InsertOutcome::Replaced(1)
+        );
+        assert_eq!(same, map);
+
+        // Policies only matter when a coarser leaf covers the cell.
+        for &policy in [
+            InsertPolicy::KeepExisting,
+            InsertPolicy::Split,
+            InsertPolicy::Error,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                split.insert_with_policy(center, 2, policy).unwrap(),
+                InsertOutcome::Replaced(2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_insert_shadowed() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let paris_res7 = eiffel_tower_res12.to_parent(7).unwrap();
+        let mut map = HexTreeMap::new();
+        map.insert(paris_res7, Rc::new("Paris"));
+        let original = map.clone();
+
+        // The shadowed value is dropped rather than handed back.
+        let eiffel_tower = Rc::new("Eiffel Tower");
+        assert_eq!(map.insert(eiffel_tower_res12, eiffel_tower.clone()), None);
+        assert_eq!(Rc::strong_count(&eiffel_tower), 1);
+        assert_eq!(map, original);
+        assert_eq!(map.get(eiffel_tower_res12).unwrap().0, paris_res7);
+    }
+
+    #[test]
+    fn test_insert_replacing() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let eiffel_tower_res9 = eiffel_tower_res12.to_parent(9).unwrap();
+        let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let mut map = HexTreeMap::new();
+        for cell in eiffel_tower_res9.children(10) {
+            map.insert(cell, cell.res());
+        }
+        // Replace one res-10 child with its res-12 descendants.
+        let res10 = eiffel_tower_res12.to_parent(10).unwrap();
+        map.remove(res10);
+        for cell in res10.children(12) {
+            map.insert(cell, cell.res());
+        }
+        map.insert(monaco_res12, 0);
+        assert_eq!(map.insert(monaco_res12, 1), Some(0));
+
+        let expected = map
+            .descendants(eiffel_tower_res9)
+            .map(|(cell, value)| (cell, *value))
+            .collect::<Vec<_>>();
+        assert_eq!(expected.len(), 6 + 49);
+        assert_eq!(map.insert_replacing(eiffel_tower_res9, 9), expected);
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.insert_replacing(eiffel_tower_res9, 99),
+            vec![(eiffel_tower_res9, 9)]
+        );
+        assert!(map.insert_replacing(eiffel_tower_res12, 12).is_empty());
+        assert_eq!(map.insert(eiffel_tower_res12, 12), None);
+        assert_eq!(map.get(eiffel_tower_res12), Some((eiffel_tower_res9, &99)));
+    }
+
+    #[test]
+    fn test_merge_pushes_down_compacted_value() {
+        use crate::compaction::EqCompactor;
+
+        let eiffel_tower_res9 = Cell::from_raw(0x891fb46741bffff).unwrap();
+        let paris_res4 = eiffel_tower_res9.to_parent(4).unwrap();
+
+        let mut coarse = HexTreeMap::with_compactor(EqCompactor);
+        coarse.insert(paris_res4, 1);
+        let mut fine = HexTreeMap::new();
+        fine.insert(eiffel_tower_res9, 2);
+
+        let mut sum = coarse.clone();
+        let mut calls = Vec::new();
+        sum.merge(&fine, |cell, a, b| {
+            calls.push(cell);
+            a + b
+        });
+        assert_eq!(calls, vec![eiffel_tower_res9]);
+        assert_eq!(sum.get(eiffel_tower_res9), Some((eiffel_tower_res9, &3)));
+        // Every resolution between 5 and 9 leaves behind the 6
+        // siblings of the merged cell's ancestor, which keep the
+        // coarse value.
+        assert_eq!(sum.len(), 6 * 5 + 1);
+        assert!(sum
+            .iter()
+            .filter(|(cell, _)| *cell != eiffel_tower_res9)
+            .all(|(_, v)| *v == 1));
+
+        // Merging in the other direction resolves the same cell.
+        let mut sum = fine.clone().replace_compactor(EqCompactor);
+        sum.merge(&coarse, |_cell, a, b| a + b);
+        assert_eq!(sum.get(eiffel_tower_res9), Some((eiffel_tower_res9, &3)));
+        assert_eq!(sum.len(), 6 * 5 + 1);
+
+        // Resolving to the coarse value lets the destination's
+        // compactor coalesce everything back into the res-4 cell.
+        let mut keep = coarse.clone();
+        keep.merge(&fine, |_cell, a, _b| *a);
+        assert_eq!(keep, coarse);
+    }
+
+    #[test]
+    fn test_merge_into_empty_compacts() {
+        use crate::compaction::EqCompactor;
+
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let mut siblings = HexTreeMap::new();
+        for cell in parent.children(3) {
+            siblings.insert(cell, 1);
+        }
+        assert_eq!(siblings.len(), 7);
+
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        map.merge(&siblings, |_cell, _a, _b| unreachable!());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(parent), Some((parent, &1)));
+    }
+
+    #[test]
+    fn test_merge_disjoint() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let mut paris = HexTreeMap::new();
+        paris.insert(eiffel_tower_res12, "Paris");
+        let mut monaco = HexTreeMap::new();
+        monaco.insert(monaco_res12, "Monaco");
+
+        paris.merge(&monaco, |_cell, _a, _b| unreachable!());
+        assert_eq!(paris.len(), 2);
+        assert_eq!(paris[eiffel_tower_res12], "Paris");
+        assert_eq!(paris[monaco_res12], "Monaco");
+    }
+
+    #[test]
+    fn test_remove_subtree() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let eiffel_tower_res9 = eiffel_tower_res12.to_parent(9).unwrap();
+        let mut map = HexTreeMap::new();
+        map.insert(eiffel_tower_res12, 12);
+        map.insert(eiffel_tower_res12.to_parent(10).unwrap(), 10);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(eiffel_tower_res9), None);
+        assert_eq!(
+            map.get(eiffel_tower_res12),
+            Some((eiffel_tower_res12.to_parent(10).unwrap(), &10))
+        );
+        assert_eq!(map.drain_descendants(eiffel_tower_res9).count(), 1);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_into_iter_and_drain() {
+        // Values are moved out, so they needn't be `Clone`.
+        struct NotClone(u64);
+
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let new_map = || {
+            let mut map = HexTreeMap::new();
+            for cell in parent.children(4).chain(std::iter::once(monaco_res12)) {
+                map.insert(cell, NotClone(cell.into_raw()));
+            }
+            map
+        };
+        let expected = new_map().iter().map(|(cell, _)| cell).collect::<Vec<_>>();
+        assert_eq!(expected.len(), 50);
+
+        let owned = new_map()
+            .into_iter()
+            .map(|(cell, value)| {
+                assert_eq!(cell.into_raw(), value.0);
+                cell
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(owned, expected);
+
+        let mut map = new_map();
+        let mut borrowed = Vec::new();
+        for (cell, value) in &mut map {
+            value.0 += 1;
+            borrowed.push(cell);
+        }
+        assert_eq!(borrowed, expected);
+        for (cell, value) in &map {
+            assert_eq!(cell.into_raw() + 1, value.0);
+        }
+
+        let mut drained = map.drain();
+        assert_eq!(drained.next().map(|(cell, _)| cell), Some(expected[0]));
+        drop(drained);
+        assert!(map.is_empty());
+        map.insert(monaco_res12, NotClone(0));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_drain_descendants() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let eiffel_tower_res9 = eiffel_tower_res12.to_parent(9).unwrap();
+        let eiffel_tower_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+
+        let mut map = HexTreeMap::new();
+        for cell in eiffel_tower_res9.children(11) {
+            map.insert(cell, 11);
+        }
+        for sibling in eiffel_tower_res9.to_parent(8).unwrap().children(9) {
+            if sibling != eiffel_tower_res9 {
+                map.insert(sibling, 9);
+            }
+        }
+        let expected = map
+            .descendants(eiffel_tower_res9)
+            .map(|(cell, value)| (cell, *value))
+            .collect::<Vec<_>>();
+        assert_eq!(expected.len(), 49);
+        let drained = map.drain_descendants(eiffel_tower_res9).collect::<Vec<_>>();
+        assert_eq!(drained, expected);
+        assert_eq!(map.descendants(eiffel_tower_res9).count(), 0);
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.drain_descendants(eiffel_tower_res9).count(), 0);
+
+        // Draining a cell under a coarser leaf splits the leaf.
+        let mut set: crate::HexTreeSet = std::iter::once(eiffel_tower_res3).collect();
+        assert_eq!(
+            set.drain_descendants(eiffel_tower_res12)
+                .collect::<Vec<_>>(),
+            vec![(eiffel_tower_res12, ())]
+        );
+        assert!(!set.contains(eiffel_tower_res12));
+        assert_eq!(set.len(), 6 * 9);
+
+        // Draining a whole base cell prunes it.
+        let base = eiffel_tower_res3.to_parent(0).unwrap();
+        assert_eq!(set.drain_descendants(base).count(), 6 * 9);
+        assert!(set.nodes.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_retain() {
+        use crate::compaction::EqCompactor;
+
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let eiffel_tower_res9 = eiffel_tower_res12.to_parent(9).unwrap();
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        for (i, cell) in eiffel_tower_res9.children(11).enumerate() {
+            map.insert(cell, i);
+        }
+        map.insert(monaco_res12, 1000);
+        assert_eq!(map.len(), 50);
+
+        let mut visited = Vec::new();
+        map.retain(|cell, value| {
+            visited.push(cell);
+            *value % 7 != 0
+        });
+        assert!(visited.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(visited.len(), 50);
+        assert_eq!(map.len(), 43);
+        assert!(map.iter().all(|(_, value)| value % 7 != 0));
+        assert!(map.contains(monaco_res12));
+
+        // Rejecting an entire subtree prunes it up to the base cell.
+        map.retain(|cell, _| !cell.is_related_to(&eiffel_tower_res9));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(monaco_res12, &1000)]);
+        assert_eq!(map.nodes.iter().filter(|node| node.is_some()).count(), 1);
+
+        // Values made equal by `f` are compacted.
+        let mut map = HexTreeMap::with_compactor(EqCompactor);
+        for (i, cell) in eiffel_tower_res9.children(11).enumerate() {
+            map.insert(cell, i);
+        }
+        map.retain(|_, value| {
+            *value = 0;
+            true
+        });
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(eiffel_tower_res9, &0)]
+        );
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let eiffel_tower_res9 = eiffel_tower_res12.to_parent(9).unwrap();
+        let monaco_res12 = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let mut map: HexTreeMap<u64> = eiffel_tower_res9
+            .children(11)
+            .chain(std::iter::once(monaco_res12))
+            .map(|cell| (cell, cell.into_raw()))
+            .collect();
+        let original = map.clone();
+
+        // Dropping the iterator early leaves the rest untouched.
+        let mut tested = 0;
+        let first = map
+            .extract_if(|_, _| {
+                tested += 1;
+                true
+            })
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(tested, 3);
+        assert_eq!(
+            first,
+            original
+                .iter()
+                .take(3)
+                .map(|(cell, value)| (cell, *value))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(map.len(), 47);
+
+        let extracted = map
+            .extract_if(|cell, value| {
+                *value += 1;
+                cell.is_related_to(&eiffel_tower_res9)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(extracted.len(), 46);
+        assert!(extracted
+            .iter()
+            .all(|(cell, value)| cell.into_raw() + 1 == *value));
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(monaco_res12, &(monaco_res12.into_raw() + 1))]
+        );
+        assert_eq!(map.nodes.iter().filter(|node| node.is_some()).count(), 1);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_get_point_after_modifying() {
+        let monaco_res6 = Cell::from_raw(0x863969a47ffffff).unwrap();
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let (lat, lng) = eiffel_tower_res12.to_latlng();
+        let mut map = HexTreeMap::new();
+        map.insert(monaco_res6, "Monaco");
+        let original = map.clone();
+        assert_eq!(map.get_point(lat, lng), None);
+        assert_eq!(map, original);
+
+        // The finest resolution is looked up again after inserting.
+        map.insert(eiffel_tower_res12, "Eiffel Tower");
+        assert_eq!(
+            map.get_point(lat, lng),
+            Some((eiffel_tower_res12, &"Eiffel Tower"))
+        );
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_nearest() {
+        let device = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        assert_eq!(HexTreeMap::<()>::new().nearest(device), None);
+
+        // A coarse leaf is as near as the nearest cell it contains, and
+        // a fine one as near as the cell containing it.
+        let ring4 = device.grid_ring(4);
+        let fine = ring4[0].center_child(15).unwrap();
+        let coarse = device.grid_ring(9)[0].to_parent(10).unwrap();
+        let mut map = HexTreeMap::new();
+        map.insert(fine, "fine");
+        map.insert(coarse, "coarse");
+        assert_eq!(
+            map.nearest(device),
+            Some((fine, &"fine", Distance::Exact(4)))
+        );
+        assert_eq!(
+            map.nearest(coarse.center_child(12).unwrap()),
+            Some((coarse, &"coarse", Distance::Exact(0)))
+        );
+
+        let coarse_distance = coarse
+            .children(12)
+            .filter_map(|child| device.grid_distance(&child))
+            .min()
+            .unwrap();
+        assert!(coarse_distance > 4 && coarse_distance <= 9);
+        assert_eq!(
+            map.k_nearest(device, 5),
+            vec![
+                (fine, &"fine", Distance::Exact(4)),
+                (coarse, &"coarse", Distance::Exact(coarse_distance))
+            ]
+        );
+        assert!(map.k_nearest(device, 0).is_empty());
+
+        // Rings step around pentagons too.
+        let pentagon = Cell::from_raw(0x8009fffffffffff)
+            .unwrap()
+            .center_child(8)
+            .unwrap();
+        let mut map = HexTreeMap::new();
+        for (i, cell) in pentagon.grid_ring(6).into_iter().enumerate() {
+            map.insert(cell, i);
+        }
+        let nearest = map.k_nearest(pentagon, map.len());
+        assert_eq!(nearest.len(), 6 * 5);
+        assert!(nearest
+            .iter()
+            .all(|&(_, _, distance)| distance == Distance::Exact(6)));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_nearest_far_away() {
+        let device = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let (lat, lng) = device.to_latlng();
+
+        // About 40 km north, thousands of res-12 rings away.
+        let far = Cell::from_latlng(lat + 0.36, lng, 12).unwrap();
+        let distance = device.grid_distance(&far).unwrap();
+        assert!(distance > 2000);
+        let mut map = HexTreeMap::new();
+        map.insert(far, "far");
+        assert_eq!(
+            map.nearest(device),
+            Some((far, &"far", Distance::Exact(distance)))
+        );
+
+        // A coarse leaf nearer than that is found through its nearest
+        // part.
+        let coarse = Cell::from_latlng(lat - 0.1, lng + 0.1, 6).unwrap();
+        let coarse_distance = device.grid_distance(&coarse.center_child(12).unwrap());
+        map.insert(coarse, "coarse");
+        let (cell, value, distance) = map.nearest(device).unwrap();
+        assert_eq!((cell, value), (coarse, &"coarse"));
+        assert!(distance.is_exact() && distance.rings() < coarse_distance.unwrap());
+
+        // Leaves too far away to measure in rings are still found, by
+        // their estimated distance.
+        let new_york = Cell::from_latlng(40.7128, -74.0060, 12).unwrap();
+        let sydney = Cell::from_latlng(-33.8568, 151.2153, 12).unwrap();
+        assert_eq!(device.grid_distance(&new_york), None);
+        assert_eq!(device.grid_distance(&sydney), None);
+        let mut map = HexTreeMap::new();
+        map.insert(sydney, "Sydney");
+        map.insert(new_york.to_parent(4).unwrap(), "New York");
+        let nearest = map.k_nearest(device, 2);
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|&(_, value, _)| *value)
+                .collect::<Vec<_>>(),
+            vec!["New York", "Sydney"]
+        );
+        assert!(nearest.iter().all(|(_, _, distance)| !distance.is_exact()));
+        assert!(nearest[0].2.rings() > 100_000 && nearest[0].2.rings() < nearest[1].2.rings());
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_k_nearest_matches_brute_force() {
+        let device = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let origin = device.to_parent(10).unwrap();
+        let (lat, lng) = device.to_latlng();
+
+        // Leaves of mixed resolutions scattered around the device.
+        let mut map = HexTreeMap::new();
+        for i in 0..12i32 {
+            for j in 0..12i32 {
+                let res = 7 + (i + j) as u8 % 6;
+                let lat = lat + f64::from(i - 6) * 0.013;
+                let lng = lng + f64::from(j - 6) * 0.017;
+                map.insert(Cell::from_latlng(lat, lng, res).unwrap(), i * 12 + j);
+            }
+        }
+
+        let distance = |leaf: Cell| match leaf.to_parent(10) {
+            Some(ancestor) => origin.grid_distance(&ancestor),
+            None => leaf
+                .children(10)
+                .filter_map(|child| origin.grid_distance(&child))
+                .min(),
+        };
+        let mut expected = map
+            .iter()
+            .map(|(leaf, value)| (leaf, value, Distance::Exact(distance(leaf).unwrap())))
+            .collect::<Vec<_>>();
+        expected.sort_by_key(|&(leaf, _, distance)| (distance.rings(), leaf));
+        assert_eq!(map.k_nearest(origin, map.len()), expected);
+        assert_eq!(map.k_nearest(origin, 10), expected[..10]);
+    }
 }
@@ -1,5 +1,13 @@
-use crate::{compaction::SetCompactor, Cell, HexTreeMap};
-use std::iter::FromIterator;
+use crate::{
+    cell::{CellStack, K_AXIS_DIGIT},
+    compaction::SetCompactor,
+    node::Node,
+    Cell, HexTreeMap,
+};
+use std::{
+    iter::FromIterator,
+    ops::{BitAnd, BitOr, BitXor, Sub},
+};
 
 /// A HexTreeSet is a structure for representing geographical regions
 /// and efficiently performing hit-tests on that region. Or,
@@ -71,3 +79,750 @@ impl<'a> FromIterator<&'a Cell> for HexTreeSet {
         set
     }
 }
+
+impl HexTreeSet {
+    /// Returns `true` if this set covers the point at `lat`, `lng`
+    /// degrees.
+    ///
+    /// See [`HexTreeMap::get_point`] for how the point is looked up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let monaco: HexTreeSet = std::iter::once(Cell::from_raw(0x863969a47ffffff)?).collect();
+    /// assert!(monaco.contains_point(43.73631, 7.42418));
+    /// assert!(!monaco.contains_point(43.73631, 8.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn contains_point(&self, lat: f64, lng: f64) -> bool {
+        self.get_point(lat, lng).is_some()
+    }
+
+    /// Returns `true` if this set overlaps any cell at most `k` steps
+    /// from `center`.
+    ///
+    /// Cells are checked nearest first, and the search stops at the
+    /// first one overlapping the set, whether the set has that cell,
+    /// one of its parents, or only some of its children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let device = Cell::from_raw(0x8c3969a41da15ff)?;
+    /// let covered = device.grid_ring(2)[0];
+    /// let set: HexTreeSet = std::iter::once(covered).collect();
+    ///
+    /// assert!(!set.intersects_disk(device, 1));
+    /// assert!(set.intersects_disk(device, 2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn intersects_disk(&self, center: Cell, k: u32) -> bool {
+        crate::geo::Disk::new(center, k).any(|(cell, _)| self.descendants(cell).next().is_some())
+    }
+
+    /// Returns the set of cells covering `polygon`, none finer than
+    /// `max_res`.
+    ///
+    /// `polygon` is in (`x` = longitude, `y` = latitude) degrees, with
+    /// straight edges in that space, and must not cross the
+    /// antimeridian. `mode` decides which `max_res` cells along its
+    /// boundary are part of the set.
+    ///
+    /// Rather than enumerating every `max_res` cell, base cells are
+    /// subdivided only where their descendants may straddle the
+    /// polygon's boundary, and kept whole wherever they are entirely
+    /// inside it. So the set is built already compacted, and the work
+    /// is proportional to the length of the boundary rather than the
+    /// polygon's area.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Resolution`] if `max_res` is greater than 15,
+    /// or [`Error::LatLng`] if any coordinate is not finite.
+    ///
+    /// [`Error::Resolution`]: crate::Error::Resolution
+    /// [`Error::LatLng`]: crate::Error::LatLng
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{geo_types::polygon, ContainmentMode, HexTreeSet};
+    ///
+    /// let monaco = polygon![
+    ///     (x: 7.4091, y: 43.7247),
+    ///     (x: 7.4396, y: 43.7476),
+    ///     (x: 7.4309, y: 43.7578),
+    ///     (x: 7.4091, y: 43.7247),
+    /// ];
+    /// let set = HexTreeSet::from_polygon(&monaco, 12, ContainmentMode::ContainsCentroid)?;
+    /// assert!(set.contains_point(43.7425, 7.4300));
+    /// assert!(!set.contains_point(43.7300, 7.4300));
+    /// // Interior cells are coarser than res 12.
+    /// assert!(set.iter().any(|(cell, _)| cell.res() < 12));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn from_polygon(
+        polygon: &geo_types::Polygon<f64>,
+        max_res: u8,
+        mode: crate::ContainmentMode,
+    ) -> crate::Result<Self> {
+        let mut set = HexTreeSet::with_compactor(SetCompactor);
+        crate::geo::polyfill(polygon, max_res, mode, |cell| {
+            set.insert(cell, ());
+        })?;
+        Ok(set)
+    }
+
+    /// Returns the outline of the area covered by this set.
+    ///
+    /// Adjacent cells are dissolved into polygons, with holes wherever
+    /// the set surrounds uncovered cells. As a cell's children don't
+    /// exactly fill its hexagon, the outline follows the edges of
+    /// cells at this set's finest resolution, which is also where
+    /// leaves of that resolution are. Coarser leaves are only expanded
+    /// along their uncovered edges, so a compacted set with a large
+    /// interior is cheap to outline.
+    ///
+    /// Coordinates are `x` = longitude and `y` = latitude degrees.
+    /// Exterior rings are counterclockwise and holes clockwise, as
+    /// GeoJSON expects. Rings crossing the antimeridian are not split,
+    /// but continue past ±180° longitude. A ring around a pole is
+    /// closed along the meridians at its ends and the pole itself, and
+    /// a set with holes but no ring around them is outlined as the
+    /// whole world with those holes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let parent = Cell::from_raw(0x863969a47ffffff)?;
+    /// let center = parent.center_child(7).unwrap();
+    /// // The 6 cells around `center`.
+    /// let ring: HexTreeSet = parent.children(7).filter(|&cell| cell != center).collect();
+    ///
+    /// let outline = ring.to_multipolygon();
+    /// assert_eq!(outline.0.len(), 1);
+    /// assert_eq!(outline.0[0].interiors().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn to_multipolygon(&self) -> geo_types::MultiPolygon<f64> {
+        match self.finest_res() {
+            Some(res) => crate::geo::dissolve(self.iter().map(|(cell, _)| cell), res, |cell| {
+                self.contains(cell)
+            }),
+            None => geo_types::MultiPolygon(Vec::new()),
+        }
+    }
+
+    /// An iterator visiting every cell at resolution `res` covered by
+    /// this set.
+    ///
+    /// See [`HexTreeMap::iter_at_res`] for how coarser and finer cells
+    /// are handled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `res` is greater than 15.
+    pub fn cells_at_res(&self, res: u8) -> impl Iterator<Item = Cell> + '_ {
+        self.iter_at_res(res).map(|(cell, _)| cell)
+    }
+
+    /// Returns a new set containing every cell covered by `self`, by
+    /// `other`, or by both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+    /// let a: HexTreeSet = std::iter::once(paris_res3).collect();
+    /// let b: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+    ///
+    /// assert_eq!(a.union(&b), a);
+    /// assert_eq!(&a | &b, a);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        combine_sets(self, other, |a, b| a || b)
+    }
+
+    /// Returns a new set containing only the cells covered by both
+    /// `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+    /// let a: HexTreeSet = std::iter::once(paris_res3).collect();
+    /// let b: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+    ///
+    /// assert_eq!(a.intersection(&b), b);
+    /// assert_eq!(&a & &b, b);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        combine_sets(self, other, |a, b| a && b)
+    }
+
+    /// Returns a new set containing the cells covered by `self` but
+    /// not by `other`.
+    ///
+    /// Leaves in `self` which are only partially covered by `other`
+    /// are split into their children, so the result may contain cells
+    /// at a finer resolution than either input leaf.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+    /// let a: HexTreeSet = std::iter::once(paris_res3).collect();
+    /// let b: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+    ///
+    /// let diff = a.difference(&b);
+    /// assert!(!diff.contains(eiffel_tower_res12));
+    /// assert!(diff.is_disjoint(&b));
+    /// assert_eq!(&a - &b, diff);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn difference(&self, other: &Self) -> Self {
+        combine_sets(self, other, |a, b| a && !b)
+    }
+
+    /// Returns a new set containing the cells covered by exactly one
+    /// of `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> hextree::Result<()> {
+    /// use hextree::{Cell, HexTreeSet};
+    ///
+    /// let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff)?;
+    /// let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+    /// let a: HexTreeSet = std::iter::once(paris_res3).collect();
+    /// let b: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+    ///
+    /// assert_eq!(a.symmetric_difference(&b), a.difference(&b));
+    /// assert_eq!(&a ^ &b, a.difference(&b));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        combine_sets(self, other, |a, b| a != b)
+    }
+}
+
+impl BitOr<&HexTreeSet> for &HexTreeSet {
+    type Output = HexTreeSet;
+
+    /// Returns the union of `self` and `rhs` as a new `HexTreeSet`.
+    fn bitor(self, rhs: &HexTreeSet) -> HexTreeSet {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd<&HexTreeSet> for &HexTreeSet {
+    type Output = HexTreeSet;
+
+    /// Returns the intersection of `self` and `rhs` as a new
+    /// `HexTreeSet`.
+    fn bitand(self, rhs: &HexTreeSet) -> HexTreeSet {
+        self.intersection(rhs)
+    }
+}
+
+impl Sub<&HexTreeSet> for &HexTreeSet {
+    type Output = HexTreeSet;
+
+    /// Returns the difference of `self` and `rhs` as a new
+    /// `HexTreeSet`.
+    fn sub(self, rhs: &HexTreeSet) -> HexTreeSet {
+        self.difference(rhs)
+    }
+}
+
+impl BitXor<&HexTreeSet> for &HexTreeSet {
+    type Output = HexTreeSet;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new
+    /// `HexTreeSet`.
+    fn bitxor(self, rhs: &HexTreeSet) -> HexTreeSet {
+        self.symmetric_difference(rhs)
+    }
+}
+
+/// A leaf node, which covers every cell below it.
+const FULL: &Node<()> = &Node::Leaf(());
+
+/// Builds a new set by combining `a` and `b` base cell by base cell.
+///
+/// `op` decides whether a cell belongs in the output given whether it
+/// is covered by `a` and by `b`, and must return `false` when covered
+/// by neither.
+fn combine_sets(a: &HexTreeSet, b: &HexTreeSet, op: fn(bool, bool) -> bool) -> HexTreeSet {
+    let mut set = HexTreeMap::with_compactor(SetCompactor);
+    let mut cell_stack = CellStack::new();
+    for (base, (a, b)) in a.nodes.iter().zip(b.nodes.iter()).enumerate() {
+        cell_stack.push(base as u8);
+        set.nodes[base] = combine_nodes(a.as_deref(), b.as_deref(), &mut cell_stack, op);
+        cell_stack.pop();
+    }
+    set
+}
+
+/// Recursively combines two (possibly absent) nodes representing
+/// the same cell.
+///
+/// Once neither side has any further structure below it, the result
+/// is decided by `op` alone. Otherwise, a leaf on one side is treated
+/// as a parent of full children so both sides can be walked in
+/// lockstep, and the resulting parent is compacted on the way back
+/// up.
+fn combine_nodes(
+    a: Option<&Node<()>>,
+    b: Option<&Node<()>>,
+    cell_stack: &mut CellStack,
+    op: fn(bool, bool) -> bool,
+) -> Option<Box<Node<()>>> {
+    fn children(node: Option<&Node<()>>, pentagon: bool) -> [Option<&Node<()>>; 7] {
+        match node {
+            None => [None; 7],
+            Some(Node::Leaf(())) => {
+                let mut out = [Some(FULL); 7];
+                if pentagon {
+                    out[K_AXIS_DIGIT as usize] = None;
+                }
+                out
+            }
+            Some(Node::Parent(children)) => {
+                let mut out = [None; 7];
+                for (out, child) in out.iter_mut().zip(children.iter()) {
+                    *out = child.as_deref();
+                }
+                out
+            }
+        }
+    }
+
+    match (a, b) {
+        (None | Some(Node::Leaf(())), None | Some(Node::Leaf(()))) => {
+            if op(a.is_some(), b.is_some()) {
+                Some(Box::new(Node::Leaf(())))
+            } else {
+                None
+            }
+        }
+        _ => {
+            let cell = *cell_stack.cell().expect("corrupted cell-stack");
+            let mut node = Node::new();
+            if let Node::Parent(out) = &mut node {
+                let pentagon = cell.is_pentagon();
+                let (a, b) = (children(a, pentagon), children(b, pentagon));
+                let pairs = a.iter().copied().zip(b.iter().copied());
+                for (digit, (out, (a, b))) in out.iter_mut().zip(pairs).enumerate() {
+                    cell_stack.push(digit as u8);
+                    *out = combine_nodes(a, b, cell_stack, op);
+                    cell_stack.pop();
+                }
+            }
+            if node.is_empty() {
+                return None;
+            }
+            node.coalesce(cell, &mut SetCompactor);
+            Some(Box::new(node))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use h3_lorawan_regions::nocompact::US915 as US915_INDICES;
+
+    #[test]
+    fn test_set_ops_mixed_resolutions() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+        let coarse: HexTreeSet = std::iter::once(paris_res3).collect();
+        let fine: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+
+        assert_eq!(coarse.union(&fine), coarse);
+        assert_eq!(fine.union(&coarse), coarse);
+        assert_eq!(coarse.intersection(&fine), fine);
+        assert_eq!(fine.intersection(&coarse), fine);
+        assert!(fine.difference(&coarse).is_empty());
+
+        let expected = {
+            let mut expected = coarse.clone();
+            expected.remove(eiffel_tower_res12);
+            expected
+        };
+        assert_eq!(coarse.difference(&fine), expected);
+        assert_eq!(coarse.symmetric_difference(&fine), expected);
+        assert_eq!(fine.symmetric_difference(&coarse), expected);
+
+        // Adding back what we removed must compact all the way up to
+        // the original res-3 cell.
+        assert_eq!(coarse.difference(&fine).union(&fine), coarse);
+    }
+
+    #[test]
+    fn test_predicates_mixed_resolutions() {
+        let eiffel_tower_res12 = Cell::from_raw(0x8c1fb46741ae9ff).unwrap();
+        let paris_res3 = eiffel_tower_res12.to_parent(3).unwrap();
+        let coarse: HexTreeSet = std::iter::once(paris_res3).collect();
+        let fine: HexTreeSet = std::iter::once(eiffel_tower_res12).collect();
+        let empty = HexTreeSet::with_compactor(SetCompactor);
+
+        assert!(fine.is_subset(&coarse));
+        assert!(!coarse.is_subset(&fine));
+        assert!(coarse.is_superset(&fine));
+        assert!(coarse.intersects(&fine) && fine.intersects(&coarse));
+        assert!(empty.is_subset(&fine) && empty.is_disjoint(&fine));
+        assert!(!coarse.is_subset(&empty));
+
+        let remainder = &coarse - &fine;
+        assert!(remainder.is_subset(&coarse));
+        assert!(remainder.is_disjoint(&fine));
+        assert!(!coarse.is_subset(&remainder));
+    }
+
+    #[test]
+    fn test_subset_of_uncompacted_parent() {
+        // A map which never compacts may still fully cover a cell
+        // with its children.
+        let parent = Cell::from_raw(0x825997fffffffff).unwrap();
+        let children: HexTreeMap<u8> = (0x835990fffffffff..=0x835996fffffffff)
+            .step_by(0x1000000000)
+            .map(|idx| (Cell::from_raw(idx).unwrap(), 0))
+            .collect();
+        assert_eq!(children.len(), 7);
+        let coarse: HexTreeSet = std::iter::once(parent).collect();
+        assert!(coarse.is_subset(&children));
+        assert!(children.is_subset(&coarse));
+    }
+
+    #[test]
+    fn test_set_ops_pentagon() {
+        // Base cell 4 is a pentagon.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let coarse: HexTreeSet = std::iter::once(pentagon).collect();
+        let center: HexTreeSet = pentagon.center_child(3).into_iter().collect();
+
+        let remainder = &coarse - &center;
+        // A pentagon's center child is also a pentagon, so every
+        // resolution leaves behind 5 siblings.
+        assert_eq!(remainder.len(), 5 * 3);
+        assert!(remainder
+            .iter()
+            .all(|(cell, _)| Cell::from_raw(cell.into_raw()).is_ok()));
+        assert_eq!(&remainder | &center, coarse);
+        assert!(!coarse.is_subset(&remainder));
+        // The 6 children of a pentagon fully cover it, even without
+        // compaction.
+        let children: HexTreeMap<()> = pentagon.children(1).map(|cell| (cell, ())).collect();
+        assert_eq!(children.len(), 6);
+        assert!(coarse.is_subset(&children));
+
+        let mut removed = coarse.clone();
+        removed.remove(pentagon.center_child(3).unwrap());
+        assert_eq!(removed, remainder);
+    }
+
+    #[test]
+    fn test_set_ops_region() {
+        let cells: Vec<Cell> = US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect();
+        let third = cells.len() / 3;
+        let (a_cells, b_cells) = (&cells[..2 * third], &cells[third..]);
+        let a: HexTreeSet = a_cells.iter().collect();
+        let b: HexTreeSet = b_cells.iter().collect();
+        let all: HexTreeSet = cells.iter().collect();
+
+        assert_eq!(&a | &b, all);
+        assert_eq!(&a & &b, cells[third..2 * third].iter().collect());
+        assert_eq!(&a - &b, cells[..third].iter().collect());
+        assert_eq!(&b - &a, cells[2 * third..].iter().collect());
+        assert_eq!(&a ^ &b, &(&a | &b) - &(&a & &b));
+        assert!(a.intersects(&b));
+        assert!((&a - &b).is_disjoint(&b));
+        assert!(a.is_subset(&all) && b.is_subset(&all));
+        assert!(all.is_superset(&a) && !a.is_superset(&all));
+        assert!((&a & &b).is_subset(&a));
+        assert!((&all - &all).is_empty());
+        assert_eq!(&all - &HexTreeSet::with_compactor(SetCompactor), all);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_contains_point_region() {
+        use byteorder::{LittleEndian as LE, ReadBytesExt};
+        let idx_bytes = include_bytes!("../assets/monaco.res12.h3idx");
+        let rdr = &mut idx_bytes.as_slice();
+        let mut monaco = HexTreeSet::with_compactor(SetCompactor);
+        while let Ok(idx) = rdr.read_u64::<LE>() {
+            monaco.insert(Cell::from_raw(idx).unwrap(), ());
+        }
+
+        let (mut inside, mut mismatched) = (0, 0);
+        for lat in 0..100 {
+            for lng in 0..100 {
+                let lat = 43.72 + f64::from(lat) * 0.0003;
+                let lng = 7.40 + f64::from(lng) * 0.0005;
+                let cell = Cell::from_latlng(lat, lng, 12).unwrap();
+                if monaco.contains_point(lat, lng) {
+                    inside += 1;
+                }
+                if monaco.contains_point(lat, lng) != monaco.contains(cell) {
+                    mismatched += 1;
+                }
+            }
+        }
+        assert!(inside > 0);
+        assert_eq!(mismatched, 0);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_from_polygon_matches_h3o() {
+        use crate::ContainmentMode;
+        use geo::LineString;
+        use h3o::{
+            geom::{ContainmentMode as H3oMode, PolyfillConfig, Polygon, ToCells},
+            Resolution,
+        };
+
+        // Around Paris, with a hole in the middle.
+        let paris = geo::Polygon::new(
+            LineString::from(vec![(2.0, 48.5), (2.8, 48.5), (2.8, 49.1), (2.0, 49.1)]),
+            vec![LineString::from(vec![
+                (2.3, 48.7),
+                (2.3, 48.9),
+                (2.5, 48.9),
+                (2.5, 48.7),
+            ])],
+        );
+        let modes = [
+            (ContainmentMode::ContainsCentroid, H3oMode::ContainsCentroid),
+            (ContainmentMode::ContainsBoundary, H3oMode::ContainsBoundary),
+            (
+                ContainmentMode::IntersectsBoundary,
+                H3oMode::IntersectsBoundary,
+            ),
+        ];
+        for (mode, h3o_mode) in modes {
+            let set = HexTreeSet::from_polygon(&paris, 8, mode).unwrap();
+            let expected: HexTreeSet = Polygon::from_degrees(paris.clone())
+                .unwrap()
+                .to_cells(PolyfillConfig::new(Resolution::Eight).containment_mode(h3o_mode))
+                .map(|cell| Cell::from_raw(u64::from(cell)).unwrap())
+                .collect();
+            assert_eq!(set, expected, "{:?}", mode);
+            assert!(set.iter().any(|(cell, _)| cell.res() < 8));
+        }
+
+        assert!(HexTreeSet::from_polygon(&paris, 16, ContainmentMode::ContainsCentroid).is_err());
+        let mut invalid = paris;
+        invalid.exterior_mut(|ring| ring.0[1].x = f64::NAN);
+        assert!(HexTreeSet::from_polygon(&invalid, 9, ContainmentMode::ContainsCentroid).is_err());
+    }
+
+    /// Asserts outlining `set` follows the same rings as outlining
+    /// each of its cells at resolution `res`.
+    #[cfg(feature = "geo")]
+    fn assert_outline_matches_uncompacted(set: &HexTreeSet, res: u8) {
+        let cells = set
+            .iter_at_res(res)
+            .map(|(cell, _)| cell)
+            .collect::<Vec<_>>();
+        let uncompacted: HexTreeMap<()> = cells.iter().map(|&cell| (cell, ())).collect();
+        assert!(set.len() < uncompacted.len());
+
+        let rings = |outline: geo_types::MultiPolygon<f64>| {
+            let mut rings = outline
+                .0
+                .iter()
+                .flat_map(|polygon| {
+                    std::iter::once((true, polygon.exterior()))
+                        .chain(polygon.interiors().iter().map(|ring| (false, ring)))
+                })
+                .map(|(exterior, ring)| {
+                    let mut coords = ring.0[1..]
+                        .iter()
+                        .map(|c| ((c.x * 1e9).round() as i64, (c.y * 1e9).round() as i64))
+                        .collect::<Vec<_>>();
+                    coords.sort_unstable();
+                    (exterior, coords)
+                })
+                .collect::<Vec<_>>();
+            rings.sort();
+            rings
+        };
+        let expected = crate::geo::dissolve(cells.iter().copied(), res, |cell| {
+            uncompacted.contains(cell)
+        });
+        assert_eq!(rings(set.to_multipolygon()), rings(expected));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_to_multipolygon_mixed_resolutions() {
+        use byteorder::{LittleEndian as LE, ReadBytesExt};
+        let idx_bytes = include_bytes!("../assets/monaco.res12.h3idx");
+        let rdr = &mut idx_bytes.as_slice();
+        let mut monaco = HexTreeSet::with_compactor(SetCompactor);
+        while let Ok(idx) = rdr.read_u64::<LE>() {
+            monaco.insert(Cell::from_raw(idx).unwrap(), ());
+        }
+        assert_outline_matches_uncompacted(&monaco, 12);
+
+        // Class III cells around a pentagon span several icosahedron
+        // faces. Punch holes in them so there are plenty of edges.
+        let pentagon = Cell::from_raw(0x8009fffffffffff).unwrap();
+        let cells: HexTreeSet = pentagon
+            .children(5)
+            .enumerate()
+            .filter(|(i, _)| i % 11 != 0)
+            .map(|(_, cell)| cell)
+            .collect();
+        assert_outline_matches_uncompacted(&cells, 5);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_intersects_disk() {
+        let device = Cell::from_raw(0x8c3969a41da15ff).unwrap();
+        let ring2 = device.grid_ring(2);
+
+        // Covered by a parent, exactly, or only by some children.
+        let parent: HexTreeSet = std::iter::once(ring2[0].to_parent(10).unwrap()).collect();
+        let exact: HexTreeSet = std::iter::once(ring2[0]).collect();
+        let child: HexTreeSet = std::iter::once(ring2[0].center_child(15).unwrap()).collect();
+        for set in &[exact, child] {
+            assert!(!set.intersects_disk(device, 1));
+            assert!(set.intersects_disk(device, 2));
+        }
+        assert!(parent.intersects_disk(device, 2));
+        assert!(!HexTreeSet::with_compactor(SetCompactor).intersects_disk(device, 5));
+
+        // Around a pentagon, the disk has one fewer cell per ring.
+        let pentagon = Cell::from_raw(0x8009fffffffffff)
+            .unwrap()
+            .center_child(6)
+            .unwrap();
+        let ring3: HexTreeSet = pentagon.grid_ring(3).into_iter().collect();
+        assert_eq!(ring3.len(), 15);
+        assert!(!ring3.intersects_disk(pentagon, 2));
+        assert!(ring3.intersects_disk(pentagon, 3));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_to_multipolygon_hole() {
+        let parent = Cell::from_raw(0x863969a47ffffff).unwrap();
+        let center = parent.center_child(7).unwrap();
+        let ring: HexTreeSet = parent.children(7).filter(|&cell| cell != center).collect();
+
+        let outline = ring.to_multipolygon();
+        assert_eq!(outline.0.len(), 1);
+        let interiors = outline.0[0].interiors();
+        assert_eq!(interiors.len(), 1);
+
+        // The hole is exactly the uncovered center cell.
+        let hole = &interiors[0].0[1..];
+        let boundary = center.boundary();
+        assert_eq!(hole.len(), boundary.len());
+        for (lat, lng) in boundary {
+            assert!(hole
+                .iter()
+                .any(|c| (c.y - lat).abs() < 1e-9 && (c.x - lng).abs() < 1e-9));
+        }
+
+        let empty = HexTreeSet::with_compactor(SetCompactor);
+        assert!(empty.to_multipolygon().0.is_empty());
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn test_to_multipolygon_around_poles() {
+        use geo::Contains;
+        let world: HexTreeSet = (0..122)
+            .map(|base| Cell::from_raw(0x8001fffffffffff | base << 45).unwrap())
+            .collect();
+        let disk = |lat| -> HexTreeSet {
+            let pole = Cell::from_latlng(lat, 0.0, 2).unwrap();
+            pole.grid_disk(2).into_iter().collect()
+        };
+        let (north, south) = (disk(90.0), disk(-90.0));
+
+        // Whether `outline` covers a point, whichever turn of the
+        // earth its rings were unwrapped to.
+        let covers = |outline: &geo_types::MultiPolygon<f64>, (lat, lng): (f64, f64)| {
+            [-360.0, 0.0, 360.0]
+                .iter()
+                .any(|shift| outline.contains(&geo_types::Point::new(lng + shift, lat)))
+        };
+        let points = [(89.9, 45.0), (0.1, 45.0), (-89.9, 45.0)];
+        for (set, polygons, holes, covered) in [
+            (north.clone(), 1, 0, [true, false, false]),
+            (south.clone(), 1, 0, [false, false, true]),
+            (&north | &south, 2, 0, [true, false, true]),
+            (&world - &north, 1, 0, [false, true, true]),
+            (&world - &(&north | &south), 1, 1, [false, true, false]),
+        ] {
+            let outline = set.to_multipolygon();
+            assert_eq!(outline.0.len(), polygons);
+            let interiors: usize = outline.0.iter().map(|p| p.interiors().len()).sum();
+            assert_eq!(interiors, holes);
+            for (&point, &covered) in points.iter().zip(&covered) {
+                assert_eq!(covers(&outline, point), covered, "{point:?}");
+            }
+        }
+
+        // A hole with no ring around it is cut out of the whole world.
+        let device = Cell::from_raw(0x8c3969a41da15ff)
+            .unwrap()
+            .to_parent(2)
+            .unwrap();
+        let outline = world
+            .difference(&std::iter::once(device).collect())
+            .to_multipolygon();
+        assert_eq!(outline.0.len(), 1);
+        assert_eq!(outline.0[0].interiors().len(), 1);
+        assert!(!covers(&outline, device.to_latlng()));
+        assert!(covers(&outline, (0.1, 45.0)));
+    }
+}
@@ -1,5 +1,13 @@
-use crate::{cell::CellStack, node::Node, Cell};
-use std::iter::{Enumerate, FlatMap};
+use crate::{
+    cell::{CellStack, Children},
+    digits::Digits,
+    node::Node,
+    Cell, HexTreeMap,
+};
+use std::{
+    iter::{Enumerate, FlatMap},
+    ops::Bound,
+};
 
 type NodeStackIter<'a, V> = FlatMap<
     Enumerate<std::slice::Iter<'a, Option<Box<Node<V>>>>>,
@@ -22,7 +30,11 @@ fn make_node_stack_iter<'a, V>(nodes: &'a [Option<Box<Node<V>>>]) -> NodeStackIt
         .flat_map(map_fn as fn((_, &'a Option<Box<Node<V>>>)) -> Option<(_, &'a Node<V>)>)
 }
 
-pub(crate) struct Iter<'a, V> {
+/// An iterator over the cell-value pairs of a
+/// [HexTreeMap], in ascending [`Cell`] order.
+///
+/// Created by [`HexTreeMap::iter`].
+pub struct Iter<'a, V> {
     stack: Vec<NodeStackIter<'a, V>>,
     curr: Option<(usize, &'a Node<V>)>,
     cell_stack: CellStack,
@@ -44,6 +56,59 @@ impl<'a, V> Iter<'a, V> {
         }
     }
 
+    /// Returns an iterator over `base` positioned at the first leaf
+    /// greater than (or, if `inclusive`, equal to) `start`.
+    ///
+    /// Rather than visiting and discarding every preceding leaf, this
+    /// descends straight down `start`'s digits, leaving behind on the
+    /// stack the partially consumed sibling iterators of each node
+    /// along the way.
+    pub(crate) fn seek(base: &'a [Option<Box<Node<V>>>], start: Cell, inclusive: bool) -> Self {
+        let mut stack = Vec::with_capacity(16);
+        let mut cell_stack = CellStack::new();
+        let mut digits = std::iter::once(start.base()).chain(Digits::new(start));
+        let mut nodes = base;
+        let curr = loop {
+            let target = digits.next().expect("seek stops at start's res");
+            let mut iter = make_node_stack_iter(nodes);
+            let mut curr = iter.next();
+            while let Some((digit, _)) = curr {
+                if digit as u8 >= target {
+                    break;
+                }
+                curr = iter.next();
+            }
+            // Push the target digit even if there is no such node
+            // here; `next` swaps in the digit of whatever node is
+            // current, and otherwise pops it along with this level.
+            cell_stack.push(target);
+            if let Some((_, node)) = curr.filter(|(digit, _)| *digit as u8 == target) {
+                let at_start = cell_stack.cell().map(Cell::res) == Some(start.res());
+                match (node, at_start) {
+                    (Node::Parent(children), false) => {
+                        stack.push(iter);
+                        nodes = &children[..];
+                        continue;
+                    }
+                    // Leaves coarser than `start` are its ancestors,
+                    // which sort after it.
+                    (Node::Leaf(_), false) => (),
+                    (Node::Leaf(_), true) if inclusive => (),
+                    // Otherwise this is `start` itself or its
+                    // descendants, which sort before it.
+                    (_, true) => curr = iter.next(),
+                }
+            }
+            stack.push(iter);
+            break curr;
+        };
+        Self {
+            stack,
+            curr,
+            cell_stack,
+        }
+    }
+
     pub(crate) fn empty() -> Self {
         let stack = Vec::new();
         let curr = None;
@@ -100,6 +165,59 @@ impl<'a, V> Iterator for Iter<'a, V> {
     }
 }
 
+/// Wraps [Iter] to yield cells at a single resolution.
+///
+/// Leaves coarser than `res` are expanded into all their descendants
+/// at `res`, and leaves finer than `res` are truncated to their
+/// ancestor at `res`. Since the tree is walked depth-first, all leaves
+/// sharing an ancestor are visited consecutively, so deduplicating
+/// truncated cells only requires remembering the last one yielded.
+pub(crate) struct IterAtRes<'a, V> {
+    iter: Iter<'a, V>,
+    res: u8,
+    children: Option<(Children, &'a V)>,
+    last: Option<Cell>,
+}
+
+impl<'a, V> IterAtRes<'a, V> {
+    pub(crate) fn new(iter: Iter<'a, V>, res: u8) -> Self {
+        assert!(res < 16, "invalid H3 resolution {}", res);
+        Self {
+            iter,
+            res,
+            children: None,
+            last: None,
+        }
+    }
+}
+
+impl<'a, V> Iterator for IterAtRes<'a, V> {
+    type Item = (Cell, &'a V);
+
+    fn next(&mut self) -> Option<(Cell, &'a V)> {
+        if let Some((children, value)) = self.children.as_mut() {
+            if let Some(child) = children.next() {
+                return Some((child, *value));
+            }
+            self.children = None;
+        }
+        loop {
+            let (cell, value) = self.iter.next()?;
+            if cell.res() < self.res {
+                let mut children = Children::new(cell, self.res);
+                let first = children.next().expect("a cell always has children");
+                self.children = Some((children, value));
+                return Some((first, value));
+            }
+            let cell = cell.to_parent(self.res).expect("cell is at least res");
+            if self.last != Some(cell) {
+                self.last = Some(cell);
+                return Some((cell, value));
+            }
+        }
+    }
+}
+
 type NodeStackIterMut<'a, V> = FlatMap<
     Enumerate<std::slice::IterMut<'a, Option<Box<Node<V>>>>>,
     Option<(usize, &'a mut Node<V>)>,
@@ -122,7 +240,11 @@ fn make_node_stack_iter_mut<'a, V>(
     )
 }
 
-pub(crate) struct IterMut<'a, V> {
+/// A mutable iterator over the cell-value pairs of a
+/// [HexTreeMap], in ascending [`Cell`] order.
+///
+/// Created by [`HexTreeMap::iter_mut`].
+pub struct IterMut<'a, V> {
     stack: Vec<NodeStackIterMut<'a, V>>,
     curr: Option<(usize, &'a mut Node<V>)>,
     cell_stack: CellStack,
@@ -200,6 +322,124 @@ impl<'a, V> Iterator for IterMut<'a, V> {
     }
 }
 
+type NodeIntoIter<V> = Enumerate<std::array::IntoIter<Option<Box<Node<V>>>, 7>>;
+
+/// An owning iterator over the cell-value pairs of a
+/// [HexTreeMap], in ascending [`Cell`] order.
+///
+/// Created by the [`IntoIterator`] impl of
+/// [HexTreeMap] and by
+/// [`HexTreeMap::drain`]. Nodes are
+/// unboxed as they are visited, so values are moved out without
+/// cloning.
+pub struct IntoIter<V> {
+    base: Enumerate<std::vec::IntoIter<Option<Box<Node<V>>>>>,
+    stack: Vec<NodeIntoIter<V>>,
+    cell_stack: CellStack,
+}
+
+impl<V> IntoIter<V> {
+    pub(crate) fn new(base: Box<[Option<Box<Node<V>>>]>) -> Self {
+        Self {
+            base: base.into_vec().into_iter().enumerate(),
+            stack: Vec::with_capacity(16),
+            cell_stack: CellStack::new(),
+        }
+    }
+
+    /// Returns an iterator over the descendants of `cell`, where
+    /// `children` are the children of `cell`'s node.
+    pub(crate) fn children(children: [Option<Box<Node<V>>>; 7], cell: Cell) -> Self {
+        let mut stack = Vec::with_capacity(16);
+        stack.push(IntoIterator::into_iter(children).enumerate());
+        Self {
+            base: Vec::new().into_iter().enumerate(),
+            stack,
+            cell_stack: CellStack::from(cell),
+        }
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self::new(Box::new([]))
+    }
+}
+
+impl<V> Iterator for IntoIter<V> {
+    type Item = (Cell, V);
+
+    fn next(&mut self) -> Option<(Cell, V)> {
+        loop {
+            let next = match self.stack.last_mut() {
+                Some(iter) => iter.next(),
+                None => Some(self.base.next()?),
+            };
+            let (digit, node) = match next {
+                Some((digit, Some(node))) => (digit, node),
+                Some((_, None)) => continue,
+                None => {
+                    // Every level below the base holds its parent's
+                    // digit on the cell stack.
+                    self.stack.pop();
+                    self.cell_stack.pop();
+                    continue;
+                }
+            };
+            self.cell_stack.push(digit as u8);
+            match *node {
+                Node::Parent(children) => {
+                    self.stack
+                        .push(IntoIterator::into_iter(children).enumerate());
+                }
+                Node::Leaf(value) => {
+                    let cell = *self.cell_stack.cell().expect("corrupted cell-stack");
+                    self.cell_stack.pop();
+                    return Some((cell, value));
+                }
+            }
+        }
+    }
+}
+
+/// An iterator which removes and yields the cell-value pairs of a
+/// [HexTreeMap] matching a predicate, in ascending [`Cell`] order.
+///
+/// Created by [`HexTreeMap::extract_if`].
+pub struct ExtractIf<'a, V, C, F> {
+    map: &'a mut HexTreeMap<V, C>,
+    cursor: Option<Cell>,
+    pred: F,
+}
+
+impl<'a, V, C, F> ExtractIf<'a, V, C, F> {
+    pub(crate) fn new(map: &'a mut HexTreeMap<V, C>, pred: F) -> Self {
+        Self {
+            map,
+            cursor: None,
+            pred,
+        }
+    }
+}
+
+impl<'a, V, C, F> Iterator for ExtractIf<'a, V, C, F>
+where
+    F: FnMut(Cell, &mut V) -> bool,
+{
+    type Item = (Cell, V);
+
+    fn next(&mut self) -> Option<(Cell, V)> {
+        loop {
+            let start = self.cursor.map_or(Bound::Unbounded, Bound::Excluded);
+            let (cell, _) = self.map.range((start, Bound::Unbounded)).next()?;
+            self.cursor = Some(cell);
+            let (_, value) = self.map.get_mut(cell).expect("cell is a leaf of the map");
+            if (self.pred)(cell, value) {
+                let node = &mut self.map.nodes[cell.base() as usize];
+                return Node::remove_leaf(node, Digits::new(cell)).map(|value| (cell, value));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Cell, HexTreeMap};
@@ -454,4 +694,159 @@ mod tests {
             .collect::<Vec<_>>();
         assert_eq!(monaco_hextree_collect, combined_subtree_collect);
     }
+
+    #[test]
+    fn test_iter_at_res_expands_coarse_leaves() {
+        use std::convert::TryFrom;
+
+        // https://wolf-h3-viewer.glitch.me/?h3=863969a47ffffff
+        let monaco_res6_cellidx = CellIndex::try_from(0x863969a47ffffff).unwrap();
+        let monaco_res6_cell = Cell::from_raw(u64::from(monaco_res6_cellidx)).unwrap();
+        let mut map = HexTreeMap::new();
+        map.insert(monaco_res6_cell, "Monaco");
+
+        let expected = monaco_res6_cellidx
+            .children(Resolution::Nine)
+            .map(|ci| Cell::from_raw(u64::from(ci)).unwrap())
+            .collect::<Vec<_>>();
+        let actual = map.iter_at_res(9).collect::<Vec<_>>();
+        assert_eq!(actual.len(), expected.len());
+        for (expected, (actual, value)) in expected.iter().zip(actual.iter()) {
+            assert_eq!(expected, actual);
+            assert_eq!(*value, &"Monaco");
+        }
+        assert_eq!(
+            map.iter_at_res(6).collect::<Vec<_>>(),
+            map.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_iter_at_res_mixed_resolutions() {
+        use std::convert::TryFrom;
+
+        let set: crate::HexTreeSet = COMPACT_US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect();
+        assert!(set.iter().any(|(cell, _)| cell.res() < 6));
+        assert!(set.iter().any(|(cell, _)| cell.res() > 6));
+
+        let expected = {
+            let mut expected = Vec::new();
+            for (cell, _) in set.iter() {
+                if cell.res() < 6 {
+                    let cellidx = CellIndex::try_from(cell.into_raw()).unwrap();
+                    expected.extend(
+                        cellidx
+                            .children(Resolution::Six)
+                            .map(|ci| Cell::from_raw(u64::from(ci)).unwrap()),
+                    );
+                } else {
+                    expected.push(cell.to_parent(6).unwrap());
+                }
+            }
+            expected.sort_by_key(|cell| cell.into_raw());
+            expected.dedup();
+            expected
+        };
+
+        let actual = set.cells_at_res(6).collect::<Vec<_>>();
+        let actual_sorted = {
+            let mut actual_sorted = actual.clone();
+            actual_sorted.sort_by_key(|cell| cell.into_raw());
+            actual_sorted
+        };
+        assert_eq!(actual.len(), expected.len(), "no duplicates");
+        assert_eq!(actual_sorted, expected);
+    }
+
+    #[test]
+    fn test_iter_is_sorted() {
+        let set: crate::HexTreeSet = COMPACT_US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect();
+        let cells = set.iter().map(|(cell, _)| cell).collect::<Vec<_>>();
+        let mut sorted = COMPACT_US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect::<Vec<_>>();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(cells, sorted);
+    }
+
+    #[test]
+    fn test_range() {
+        use std::ops::Bound;
+
+        let set: crate::HexTreeSet = COMPACT_US915_INDICES
+            .iter()
+            .map(|&idx| Cell::from_raw(idx).unwrap())
+            .collect();
+        let cells = set.iter().map(|(cell, _)| cell).collect::<Vec<_>>();
+
+        // Bounds on leaves themselves, as well as on their ancestors,
+        // descendants, and cells outside the map entirely.
+        let mut bounds = Vec::new();
+        for &cell in cells.iter().step_by(97) {
+            bounds.push(cell);
+            if cell.res() > 0 {
+                bounds.push(cell.to_parent(cell.res() - 1).unwrap());
+            }
+            if cell.res() < 15 {
+                bounds.extend(cell.children(cell.res() + 1).step_by(3));
+            }
+        }
+        bounds.push(Cell::from_raw(0x8001fffffffffff).unwrap());
+        bounds.push(Cell::from_raw(0x80f3fffffffffff).unwrap());
+        bounds.push(Cell::from_raw(0x8c1fb46741ae9ff).unwrap());
+
+        for &start in bounds.iter() {
+            for &(start, inclusive) in [(start, true), (start, false)].iter() {
+                let start_bound = if inclusive {
+                    Bound::Included(start)
+                } else {
+                    Bound::Excluded(start)
+                };
+                let expected = cells
+                    .iter()
+                    .copied()
+                    .filter(|cell| {
+                        if inclusive {
+                            *cell >= start
+                        } else {
+                            *cell > start
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let actual = set
+                    .range((start_bound, Bound::Unbounded))
+                    .map(|(cell, _)| cell)
+                    .collect::<Vec<_>>();
+                assert_eq!(actual, expected, "{:?}", start_bound);
+            }
+        }
+
+        let (start, end) = (bounds[3], bounds[bounds.len() / 2]);
+        let expected = cells
+            .iter()
+            .copied()
+            .filter(|cell| (start..end).contains(cell))
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        let actual = set.range(start..end).map(|(cell, _)| cell);
+        assert!(actual.eq(expected.iter().copied()));
+        let actual = set.range(..=end).map(|(cell, _)| cell).collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            cells
+                .iter()
+                .copied()
+                .filter(|cell| *cell <= end)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(set.range(..).count(), cells.len());
+    }
 }
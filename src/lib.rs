@@ -8,12 +8,24 @@ mod digits;
 pub mod disktree;
 mod entry;
 mod error;
+#[cfg(feature = "geo")]
+mod geo;
+#[cfg(feature = "geojson")]
+mod geojson_io;
 pub mod hex_tree_map;
 mod hex_tree_set;
 mod iteration;
 mod node;
+#[cfg(feature = "serde")]
+pub mod serde_str;
 
 pub use crate::{cell::Cell, hex_tree_map::HexTreeMap, hex_tree_set::HexTreeSet};
-pub use error::{Error, Result};
+pub use error::{Error, InvalidCell, Result};
+#[cfg(feature = "geo")]
+pub use geo::ContainmentMode;
+#[cfg(feature = "geo")]
+pub use geo_types;
+#[cfg(feature = "geojson")]
+pub use geojson;
 #[cfg(feature = "serde")]
 pub use serde;
@@ -1,4 +1,9 @@
-use crate::{compaction::Compactor, digits::Digits, Cell};
+use crate::{
+    cell::{CellStack, K_AXIS_DIGIT},
+    compaction::{CompactionContext, ContextCompactor},
+    digits::Digits,
+    Cell,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -20,6 +25,28 @@ impl<V> Node<V> {
         }
     }
 
+    /// Returns the number of levels between `self` and its deepest
+    /// leaf, which is 0 for a leaf.
+    #[cfg(feature = "geo")]
+    pub(crate) fn depth(&self) -> u8 {
+        match self {
+            Self::Leaf(_) => 0,
+            Self::Parent(children) => {
+                1 + children
+                    .iter()
+                    .flatten()
+                    .map(|child| child.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    /// Inserts `value` for `cell`, returning the node it displaced,
+    /// or, if a coarser leaf already covers `cell`, that leaf's cell
+    /// along with the rejected `value`.
+    ///
+    /// A freshly created path displaces an empty parent.
     pub(crate) fn insert<C>(
         &mut self,
         cell: Cell,
@@ -27,55 +54,290 @@ impl<V> Node<V> {
         mut digits: Digits,
         value: V,
         compactor: &mut C,
-    ) where
-        C: Compactor<V>,
+    ) -> Result<Node<V>, (Cell, V)>
+    where
+        C: ContextCompactor<V>,
     {
-        match digits.next() {
-            None => *self = Self::Leaf(value),
+        let displaced = match digits.next() {
+            None => std::mem::replace(self, Self::Leaf(value)),
             Some(digit) => match self {
                 Self::Leaf(_) => {
-                    return;
-                }
-                Self::Parent(children) => {
-                    match children[digit as usize].as_mut() {
-                        Some(node) => node.insert(cell, res + 1, digits, value, compactor),
-                        None => {
-                            let mut node = Node::new();
-                            node.insert(cell, res + 1, digits, value, compactor);
-                            children[digit as usize] = Some(Box::new(node));
-                        }
-                    };
+                    let ancestor = cell.to_parent(res).expect("invalid condition");
+                    return Err((ancestor, value));
                 }
+                Self::Parent(children) => match children[digit as usize].as_mut() {
+                    Some(node) => node.insert(cell, res + 1, digits, value, compactor)?,
+                    None => {
+                        let mut node = Node::new();
+                        let displaced = node.insert(cell, res + 1, digits, value, compactor)?;
+                        children[digit as usize] = Some(Box::new(node));
+                        displaced
+                    }
+                },
             },
         };
         self.coalesce(cell.to_parent(res).unwrap(), compactor);
+        Ok(displaced)
     }
 
-    pub(crate) fn coalesce<C>(&mut self, cell: Cell, compactor: &mut C)
+    /// Removes the cell described by `digits` from the subtree rooted
+    /// at `node`, returning the value of the leaf which covered it.
+    ///
+    /// See [`take`][Node::take] for how coarser leaves and emptied
+    /// parents are handled. If the cell is a parent, its subtree is
+    /// left in place and `None` returned.
+    pub(crate) fn remove(
+        node: &mut Option<Box<Node<V>>>,
+        digits: Digits,
+        pentagon: bool,
+    ) -> Option<V>
     where
-        C: Compactor<V>,
+        V: Clone,
     {
-        if let Self::Parent(children) = self {
-            if children
-                .iter()
-                .any(|n| matches!(n.as_ref().map(|n| n.as_ref()), Some(Self::Parent(_))))
-            {
+        let is_leaf = |node: &Self| matches!(node, Self::Leaf(_));
+        match Self::take_if(node, digits, pentagon, is_leaf).map(|node| *node) {
+            Some(Self::Leaf(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Detaches the subtree for the cell described by `digits` from
+    /// the subtree rooted at `node`.
+    ///
+    /// A leaf coarser than the target cell is split into its
+    /// children, each carrying a clone of the leaf's value, until the
+    /// target cell is reached. Parents left without any children are
+    /// pruned on the way back up. `pentagon` must be `true` if `node`
+    /// is a pentagon.
+    pub(crate) fn take(
+        node: &mut Option<Box<Node<V>>>,
+        digits: Digits,
+        pentagon: bool,
+    ) -> Option<Box<Node<V>>>
+    where
+        V: Clone,
+    {
+        Self::take_if(node, digits, pentagon, |_| true)
+    }
+
+    /// Like [`take`][Node::take], but only detaches the target cell's
+    /// node if `pred` returns `true` for it, leaving the tree
+    /// unchanged otherwise.
+    fn take_if<P>(
+        node: &mut Option<Box<Node<V>>>,
+        mut digits: Digits,
+        pentagon: bool,
+        pred: P,
+    ) -> Option<Box<Node<V>>>
+    where
+        V: Clone,
+        P: Fn(&Self) -> bool,
+    {
+        let digit = match digits.next() {
+            None if node.as_deref().is_some_and(&pred) => return node.take(),
+            None => return None,
+            Some(digit) => digit,
+        };
+        let child_pentagon = pentagon && digit == 0;
+        let taken = match node.as_deref_mut() {
+            None => return None,
+            Some(leaf @ Self::Leaf(_)) => {
+                leaf.split(pentagon);
+                match leaf {
+                    Self::Parent(children) => {
+                        Self::take_if(&mut children[digit as usize], digits, child_pentagon, pred)
+                    }
+                    Self::Leaf(_) => unreachable!("we just split this leaf"),
+                }
+            }
+            Some(Self::Parent(children)) => {
+                Self::take_if(&mut children[digit as usize], digits, child_pentagon, pred)
+            }
+        };
+        if node.as_deref().is_some_and(Node::is_empty) {
+            *node = None;
+        }
+        taken
+    }
+
+    /// Removes the leaf for exactly the cell described by `digits`
+    /// from the subtree rooted at `node`, returning its value.
+    ///
+    /// Unlike [`take`][Node::take], this never splits a coarser leaf,
+    /// and returns `None` without modifying anything if there is no
+    /// such leaf. Parents left without any children are pruned.
+    pub(crate) fn remove_leaf(node: &mut Option<Box<Node<V>>>, mut digits: Digits) -> Option<V> {
+        let removed = match (digits.next(), node.as_deref_mut()) {
+            (None, Some(Self::Leaf(_))) => match node.take().map(|node| *node) {
+                Some(Self::Leaf(value)) => return Some(value),
+                _ => unreachable!("we just matched a leaf"),
+            },
+            (Some(digit), Some(Self::Parent(children))) => {
+                Self::remove_leaf(&mut children[digit as usize], digits)
+            }
+            _ => return None,
+        };
+        if node.as_deref().is_some_and(Node::is_empty) {
+            *node = None;
+        }
+        removed
+    }
+
+    /// Merges `other` into the subtree rooted at `node`.
+    ///
+    /// Where both sides hold a leaf for the same cell, `f` decides the
+    /// resulting value. Where one side's leaf covers the other side's
+    /// subtree, the leaf's value is pushed down into that subtree
+    /// before resolving. Every parent visited, including those in
+    /// subtrees cloned from `other` into empty slots, is coalesced
+    /// with `compactor` on the way back up.
+    pub(crate) fn merge<C, F>(
+        node: &mut Option<Box<Node<V>>>,
+        other: &Node<V>,
+        cell_stack: &mut CellStack,
+        f: &mut F,
+        compactor: &mut C,
+    ) where
+        V: Clone,
+        C: ContextCompactor<V>,
+        F: FnMut(Cell, &V, &V) -> V,
+    {
+        let node = match node {
+            Some(node) => node,
+            None => {
+                let mut other = other.clone();
+                other.compact(cell_stack, compactor);
+                *node = Some(Box::new(other));
                 return;
             }
-            let mut arr: [Option<&V>; 7] = [None, None, None, None, None, None, None];
-            for (v, n) in arr.iter_mut().zip(children.iter()) {
-                *v = n.as_ref().map(|n| n.as_ref()).and_then(Node::value);
+        };
+        let cell = *cell_stack.cell().expect("corrupted cell-stack");
+        if let (Self::Leaf(value), Self::Leaf(other_value)) = (node.as_ref(), other) {
+            let value = f(cell, value, other_value);
+            **node = Self::Leaf(value);
+            return;
+        }
+        node.split(cell.is_pentagon());
+        let other_children = match other {
+            Self::Leaf(_) => {
+                let mut out = [Some(other); 7];
+                if cell.is_pentagon() {
+                    out[K_AXIS_DIGIT as usize] = None;
+                }
+                out
             }
-            if let Some(value) = compactor.compact(cell, arr) {
-                *self = Self::Leaf(value)
+            Self::Parent(children) => {
+                let mut out = [None; 7];
+                for (out, child) in out.iter_mut().zip(children.iter()) {
+                    *out = child.as_deref();
+                }
+                out
             }
         };
+        if let Self::Parent(children) = node.as_mut() {
+            for (digit, (child, other_child)) in
+                children.iter_mut().zip(other_children.iter()).enumerate()
+            {
+                if let Some(other_child) = other_child {
+                    cell_stack.push(digit as u8);
+                    Self::merge(child, other_child, cell_stack, f, compactor);
+                    cell_stack.pop();
+                }
+            }
+        }
+        node.coalesce(cell, compactor);
+    }
+
+    /// Turns a leaf into a parent of 7 leaves, or 6 if `pentagon` is
+    /// `true`, each holding a clone of the original value.
+    fn split(&mut self, pentagon: bool)
+    where
+        V: Clone,
+    {
+        if let Self::Leaf(value) = self {
+            let value = value.clone();
+            let leaf = || Some(Box::new(Self::Leaf(value.clone())));
+            let mut children = [leaf(), leaf(), leaf(), leaf(), leaf(), leaf(), leaf()];
+            if pentagon {
+                children[K_AXIS_DIGIT as usize] = None;
+            }
+            *self = Self::Parent(children);
+        }
     }
 
-    pub(crate) fn value(&self) -> Option<&V> {
+    /// Returns `true` if this is a parent node without any children.
+    pub(crate) fn is_empty(&self) -> bool {
         match self {
-            Self::Leaf(value) => Some(value),
-            _ => None,
+            Self::Leaf(_) => false,
+            Self::Parent(children) => children.iter().all(Option::is_none),
+        }
+    }
+
+    pub(crate) fn coalesce<C>(&mut self, cell: Cell, compactor: &mut C)
+    where
+        C: ContextCompactor<V>,
+    {
+        if let Self::Parent(children) = self {
+            let ctx = CompactionContext::new(cell, children);
+            if let Some(value) = compactor.compact_with_context(&ctx) {
+                *self = Self::Leaf(value)
+            }
+        };
+    }
+
+    /// Coalesces every parent in this subtree, children before
+    /// parents, so compaction can cascade all the way up to `self`.
+    pub(crate) fn compact<C>(&mut self, cell_stack: &mut CellStack, compactor: &mut C)
+    where
+        C: ContextCompactor<V>,
+    {
+        if let Self::Parent(children) = self {
+            for (digit, child) in children.iter_mut().enumerate() {
+                if let Some(child) = child {
+                    cell_stack.push(digit as u8);
+                    child.compact(cell_stack, compactor);
+                    cell_stack.pop();
+                }
+            }
+            let cell = *cell_stack.cell().expect("corrupted cell-stack");
+            self.coalesce(cell, compactor);
+        }
+    }
+
+    /// Removes every leaf in the subtree rooted at `node` for which
+    /// `f` returns `false`, then coalesces the surviving parents with
+    /// `compactor`, children before parents. Parents left without any
+    /// children are pruned. `cell_stack` must hold `node`'s cell.
+    pub(crate) fn retain<C, F>(
+        node: &mut Option<Box<Node<V>>>,
+        cell_stack: &mut CellStack,
+        compactor: &mut C,
+        f: &mut F,
+    ) where
+        C: ContextCompactor<V>,
+        F: FnMut(Cell, &mut V) -> bool,
+    {
+        let cell = *cell_stack.cell().expect("corrupted cell-stack");
+        match node.as_deref_mut() {
+            None => (),
+            Some(Self::Leaf(value)) => {
+                let keep = f(cell, value);
+                if !keep {
+                    *node = None;
+                }
+            }
+            Some(Self::Parent(children)) => {
+                for (digit, child) in children.iter_mut().enumerate() {
+                    cell_stack.push(digit as u8);
+                    Self::retain(child, cell_stack, compactor, f);
+                    cell_stack.pop();
+                }
+                if node.as_deref().is_some_and(Node::is_empty) {
+                    *node = None;
+                } else if let Some(parent) = node.as_deref_mut() {
+                    parent.coalesce(cell, compactor);
+                }
+            }
         }
     }
 
@@ -96,6 +358,50 @@ impl<V> Node<V> {
         }
     }
 
+    /// Returns `true` if every cell covered by `self` is also covered
+    /// by `other`.
+    ///
+    /// A leaf covers every cell below it, while a parent only covers a
+    /// cell if its children, recursively, cover all of that cell's
+    /// children. `pentagon` must be `true` if `self` is a pentagon.
+    pub(crate) fn is_subset<U>(&self, other: &Node<U>, pentagon: bool) -> bool {
+        match (self, other) {
+            (_, Node::Leaf(_)) => true,
+            (Self::Leaf(_), Node::Parent(others)) => {
+                others
+                    .iter()
+                    .enumerate()
+                    .all(|(digit, other)| match other.as_deref() {
+                        None => pentagon && digit == K_AXIS_DIGIT as usize,
+                        Some(other) => self.is_subset(other, pentagon && digit == 0),
+                    })
+            }
+            (Self::Parent(children), Node::Parent(others)) => children
+                .iter()
+                .zip(others.iter())
+                .enumerate()
+                .all(|(digit, pair)| match pair {
+                    (None, _) => true,
+                    (Some(_), None) => false,
+                    (Some(child), Some(other)) => child.is_subset(other, pentagon && digit == 0),
+                }),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` cover at least one cell
+    /// in common.
+    pub(crate) fn intersects<U>(&self, other: &Node<U>) -> bool {
+        match (self, other) {
+            (Self::Leaf(_), _) | (_, Node::Leaf(_)) => true,
+            (Self::Parent(children), Node::Parent(others)) => {
+                children.iter().zip(others.iter()).any(|pair| match pair {
+                    (Some(child), Some(other)) => child.intersects(other),
+                    _ => false,
+                })
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn get(&self, res: u8, cell: Cell, mut digits: Digits) -> Option<(Cell, &Node<V>)> {
         match (digits.next(), self) {
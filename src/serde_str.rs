@@ -0,0 +1,54 @@
+//! Serialize [Cell]s as hexadecimal strings.
+//!
+//! By default, `Cell` is serialized transparently as its raw [u64]
+//! index. Use this module with serde's `with` attribute to instead use
+//! the canonical H3 string representation, which is what most
+//! CSV/JSON sources contain.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use hextree::{serde::{Deserialize, Serialize}, Cell};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! # #[serde(crate = "hextree::serde")]
+//! struct Site {
+//!     #[serde(with = "hextree::serde_str")]
+//!     cell: Cell,
+//!     name: String,
+//! }
+//!
+//! let site = Site {
+//!     cell: "8c1fb46741ae9ff".parse()?,
+//!     name: "Eiffel Tower".to_owned(),
+//! };
+//! let bytes = bincode::serialize(&site)?;
+//! assert_eq!(bincode::deserialize::<Site>(&bytes)?, site);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Cell;
+use serde::{de, Deserialize, Deserializer, Serializer};
+use std::borrow::Cow;
+
+/// Serializes `cell` as a lowercase hexadecimal string.
+pub fn serialize<S>(cell: &Cell, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(cell)
+}
+
+/// Deserializes a cell from a hexadecimal string.
+///
+/// See [Cell]'s [`FromStr`][std::str::FromStr] implementation for
+/// accepted formats.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Cell, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Cow::<'de, str>::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}